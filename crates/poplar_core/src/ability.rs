@@ -0,0 +1,602 @@
+//! Card behavior, declared per card in the `abilities` column of `cards.csv`.
+//!
+//! An ability reads `<trigger>: <effect>` optionally followed by `if <condition>`,
+//! a card lists them separated by `;`:
+//!
+//! ```text
+//! play: damage 2; tick 5: draw 1 if hand_below 3; death: spawn_ball heavy
+//! ```
+//!
+//! [`MatchState`](crate::MatchState) resolves them when their trigger fires.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ball_kind::BallKindId, error::AbilityError};
+
+/// When an ability fires. The card has to be on the battlefield, except for `Death`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    /// the card was played from the hand
+    Play,
+    /// the ball touched the card
+    BallHit,
+    /// every `every` seconds the card spent on the battlefield
+    Tick { every: f32 },
+    /// the card left the battlefield destroyed
+    Death,
+}
+
+impl Trigger {
+    /// Whether a `Tick` trigger fires as the time on the battlefield goes from `before` to `after`.
+    pub fn ticks_between(&self, before: f32, after: f32) -> bool {
+        match *self {
+            Trigger::Tick { every } => (before / every).floor() < (after / every).floor(),
+            _ => false,
+        }
+    }
+}
+
+/// Checked right before the effect resolves, from the point of view of the card owner.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    HealthBelow(i32),
+    OpponentHealthBelow(i32),
+    HandBelow(usize),
+    BallPowerAtLeast(u32),
+    GraveyardAtLeast(usize),
+    /// the ball was hit that many times since it was served
+    RallyAtLeast(u32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Cost,
+    Cooldown,
+    Attack,
+    Health,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    /// hurts the opponent of the card owner
+    Damage(i32),
+    /// the owner draws cards, as far as the hand and the deck allow
+    Draw(u32),
+    /// changes a stat of the card itself
+    Buff { stat: Stat, amount: isize },
+    /// serves another ball of that kind at the opponent, `spawn_ball` alone serves a standard one
+    SpawnBall(BallKindId),
+    /// moves the card by that many battlefield slots if the target is free
+    Shift(isize),
+    /// changes the ball speed by that many percent, undone when a field card leaves play
+    BallSpeed(isize),
+    /// returns the topmost creature of the owner's graveyard to their hand,
+    /// for a `death` ability that is the card itself
+    Recall,
+    /// makes the ball that hit the card, or every ball in play, that many percent faster for as long as it stays in play
+    SpeedUp(isize),
+    /// turns the ball that hit the card, or every ball in play, by that many degrees counterclockwise
+    Deflect(i32),
+    /// adds to the power of the ball that hit the card, or of every ball in play
+    Empower(u32),
+    /// makes the paddle of the owner that many percent wider for the rest of the match
+    PaddleWidth(isize),
+    /// makes the paddle of the owner that many percent faster for the rest of the match
+    PaddleSpeed(isize),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ability {
+    pub trigger: Trigger,
+    pub effect: Effect,
+    #[serde(default)]
+    pub condition: Option<Condition>,
+}
+
+/// Every ability of a card, kept in the text form above when serialized.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Abilities(pub Vec<Ability>);
+
+impl Abilities {
+    pub fn iter(&self) -> impl Iterator<Item = &Ability> {
+        self.0.iter()
+    }
+}
+
+fn number<T: FromStr>(word: Option<&str>) -> Result<T, AbilityError> {
+    let word = word.unwrap_or_default();
+    word.parse()
+        .map_err(|_| AbilityError::BadNumber(word.to_string()))
+}
+
+impl FromStr for Trigger {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next().unwrap_or_default() {
+            "play" => Ok(Trigger::Play),
+            "ball_hit" => Ok(Trigger::BallHit),
+            "tick" => {
+                let every: f32 = number(words.next())?;
+                if !every.is_finite() || every <= 0.0 {
+                    return Err(AbilityError::BadNumber(every.to_string()));
+                }
+                Ok(Trigger::Tick { every })
+            }
+            "death" => Ok(Trigger::Death),
+            _ => Err(AbilityError::UnknownTrigger(s.trim().to_string())),
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next().unwrap_or_default() {
+            "health_below" => Ok(Condition::HealthBelow(number(words.next())?)),
+            "opponent_health_below" => Ok(Condition::OpponentHealthBelow(number(words.next())?)),
+            "hand_below" => Ok(Condition::HandBelow(number(words.next())?)),
+            "ball_power" => Ok(Condition::BallPowerAtLeast(number(words.next())?)),
+            "graveyard" => Ok(Condition::GraveyardAtLeast(number(words.next())?)),
+            "rally" => Ok(Condition::RallyAtLeast(number(words.next())?)),
+            _ => Err(AbilityError::UnknownCondition(s.trim().to_string())),
+        }
+    }
+}
+
+impl FromStr for Stat {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cost" => Ok(Stat::Cost),
+            "cooldown" => Ok(Stat::Cooldown),
+            "attack" => Ok(Stat::Attack),
+            "health" => Ok(Stat::Health),
+            _ => Err(AbilityError::UnknownStat(s.to_string())),
+        }
+    }
+}
+
+impl FromStr for Effect {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        match words.next().unwrap_or_default() {
+            "damage" => Ok(Effect::Damage(number(words.next())?)),
+            "draw" => Ok(Effect::Draw(number(words.next())?)),
+            "buff" => {
+                let stat = words.next().unwrap_or_default().parse()?;
+                Ok(Effect::Buff {
+                    stat,
+                    amount: number(words.next())?,
+                })
+            }
+            "spawn_ball" => Ok(Effect::SpawnBall(
+                words.next().map(BallKindId::from).unwrap_or_default(),
+            )),
+            "shift" => Ok(Effect::Shift(number(words.next())?)),
+            "ball_speed" => Ok(Effect::BallSpeed(number(words.next())?)),
+            "recall" => Ok(Effect::Recall),
+            "speed_up" => Ok(Effect::SpeedUp(number(words.next())?)),
+            "deflect" => Ok(Effect::Deflect(number(words.next())?)),
+            "empower" => Ok(Effect::Empower(number(words.next())?)),
+            "paddle_width" => Ok(Effect::PaddleWidth(number(words.next())?)),
+            "paddle_speed" => Ok(Effect::PaddleSpeed(number(words.next())?)),
+            _ => Err(AbilityError::UnknownEffect(s.trim().to_string())),
+        }
+    }
+}
+
+impl FromStr for Ability {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (trigger, rest) = s
+            .split_once(':')
+            .ok_or_else(|| AbilityError::MissingTrigger(s.trim().to_string()))?;
+        let (effect, condition) = match rest.split_once(" if ") {
+            Some((effect, condition)) => (effect, Some(condition.parse()?)),
+            None => (rest, None),
+        };
+        Ok(Ability {
+            trigger: trigger.parse()?,
+            effect: effect.parse()?,
+            condition,
+        })
+    }
+}
+
+impl FromStr for Abilities {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .filter(|ability| !ability.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Abilities)
+    }
+}
+
+impl TryFrom<String> for Abilities {
+    type Error = AbilityError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Abilities> for String {
+    fn from(abilities: Abilities) -> Self {
+        abilities.to_string()
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Play => write!(f, "play"),
+            Trigger::BallHit => write!(f, "ball_hit"),
+            Trigger::Tick { every } => write!(f, "tick {}", every),
+            Trigger::Death => write!(f, "death"),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::HealthBelow(health) => write!(f, "health_below {}", health),
+            Condition::OpponentHealthBelow(health) => write!(f, "opponent_health_below {}", health),
+            Condition::HandBelow(cards) => write!(f, "hand_below {}", cards),
+            Condition::BallPowerAtLeast(power) => write!(f, "ball_power {}", power),
+            Condition::GraveyardAtLeast(cards) => write!(f, "graveyard {}", cards),
+            Condition::RallyAtLeast(hits) => write!(f, "rally {}", hits),
+        }
+    }
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stat::Cost => write!(f, "cost"),
+            Stat::Cooldown => write!(f, "cooldown"),
+            Stat::Attack => write!(f, "attack"),
+            Stat::Health => write!(f, "health"),
+        }
+    }
+}
+
+impl fmt::Display for Effect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Effect::Damage(damage) => write!(f, "damage {}", damage),
+            Effect::Draw(cards) => write!(f, "draw {}", cards),
+            Effect::Buff { stat, amount } => write!(f, "buff {} {}", stat, amount),
+            Effect::SpawnBall(kind) if kind.0 == BallKindId::STANDARD => write!(f, "spawn_ball"),
+            Effect::SpawnBall(kind) => write!(f, "spawn_ball {}", kind),
+            Effect::Shift(slots) => write!(f, "shift {}", slots),
+            Effect::BallSpeed(percent) => write!(f, "ball_speed {}", percent),
+            Effect::Recall => write!(f, "recall"),
+            Effect::SpeedUp(percent) => write!(f, "speed_up {}", percent),
+            Effect::Deflect(degrees) => write!(f, "deflect {}", degrees),
+            Effect::Empower(power) => write!(f, "empower {}", power),
+            Effect::PaddleWidth(percent) => write!(f, "paddle_width {}", percent),
+            Effect::PaddleSpeed(percent) => write!(f, "paddle_speed {}", percent),
+        }
+    }
+}
+
+impl fmt::Display for Ability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.trigger, self.effect)?;
+        if let Some(condition) = self.condition {
+            write!(f, " if {}", condition)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Abilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, ability) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", ability)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use super::*;
+    use crate::{testing, Action, BallState, CardInstanceId, MatchState, PlayerState, Seat};
+
+    // both decks are free creatures carrying `abilities`, dealt so there is a hand and a ball in play
+    fn dealt(abilities: &str) -> MatchState {
+        let row = format!(
+            "card,card,卡,creature,0,0,1,3,,card,{},card.png\n",
+            abilities
+        );
+        let registry = testing::registry(&row);
+        let mut state = testing::match_state(&registry, &["card"; 10], 11);
+        state.deal();
+        state
+    }
+
+    fn play(state: &mut MatchState) -> CardInstanceId {
+        let card = state.player(Seat::One).hand[0].id;
+        state
+            .apply(Seat::One, Action::PlayCard { card, slot: 0 })
+            .unwrap();
+        card
+    }
+
+    fn opponent_health(state: &MatchState) -> i32 {
+        state.player(Seat::Two).health
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let text = "play: damage 2; ball_hit: deflect -20 if rally 3; tick 2.5: draw 1 if hand_below 3; \
+            death: spawn_ball; death: spawn_ball heavy; play: buff health -2 if health_below 10; \
+            play: buff cost 1 if opponent_health_below 5; play: shift -1 if ball_power 2; \
+            play: ball_speed 25 if graveyard 4; death: recall; ball_hit: speed_up 10; \
+            ball_hit: empower 1; play: paddle_width 25; play: paddle_speed -10; play: buff cooldown 1; \
+            play: buff attack 3";
+        let abilities: Abilities = text.parse().unwrap();
+        assert_eq!(abilities.0.len(), 16);
+        assert_eq!(abilities.to_string(), text);
+        assert_eq!(abilities.to_string().parse::<Abilities>(), Ok(abilities));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "jump: damage 1".parse::<Abilities>(),
+            Err(AbilityError::UnknownTrigger("jump".to_string()))
+        );
+        assert_eq!(
+            "damage 1".parse::<Abilities>(),
+            Err(AbilityError::MissingTrigger("damage 1".to_string()))
+        );
+        assert_eq!(
+            "tick 0: damage 1".parse::<Abilities>(),
+            Err(AbilityError::BadNumber("0".to_string()))
+        );
+        assert_eq!(
+            "tick nan: damage 1".parse::<Abilities>(),
+            Err(AbilityError::BadNumber("NaN".to_string()))
+        );
+        assert_eq!(
+            "tick inf: damage 1".parse::<Abilities>(),
+            Err(AbilityError::BadNumber("inf".to_string()))
+        );
+    }
+
+    #[test]
+    fn ticks_between() {
+        let tick = Trigger::Tick { every: 2.0 };
+        assert!(!tick.ticks_between(0.0, 1.99));
+        assert!(tick.ticks_between(1.99, 2.0));
+        assert!(!tick.ticks_between(2.0, 3.99));
+        assert!(tick.ticks_between(3.99, 4.01));
+        assert!(tick.ticks_between(0.0, 10.0));
+        assert!(!Trigger::Play.ticks_between(0.0, 10.0));
+    }
+
+    #[test]
+    fn play_trigger() {
+        let mut state = dealt("play: damage 2");
+        play(&mut state);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 2);
+    }
+
+    #[test]
+    fn ball_hit_trigger() {
+        let mut state = dealt("ball_hit: damage 2");
+        let card = play(&mut state);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH);
+        let ball = state.balls[0].id;
+        state.ball_hit(card, ball);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 2);
+    }
+
+    #[test]
+    fn tick_trigger() {
+        let mut state = dealt("tick 2: damage 1");
+        play(&mut state);
+        state.tick_abilities(1.5);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH);
+        state.tick_abilities(0.5);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 1);
+        state.tick_abilities(1.0);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 1);
+        state.tick_abilities(1.0);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 2);
+    }
+
+    #[test]
+    fn death_trigger() {
+        let mut state = dealt("death: damage 3");
+        let card = play(&mut state);
+        state.damage_card(card, 2);
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH);
+        state.damage_card(card, 1);
+        assert!(state.player(Seat::One).graveyard.contains(card));
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 3);
+    }
+
+    // whether `play: damage 1 if <condition>` fires once `setup` ran
+    fn fires(condition: &str, setup: impl FnOnce(&mut MatchState)) -> bool {
+        let mut state = dealt(&format!("play: damage 1 if {}", condition));
+        setup(&mut state);
+        let before = opponent_health(&state);
+        play(&mut state);
+        opponent_health(&state) < before
+    }
+
+    #[test]
+    fn health_below() {
+        assert!(fires("health_below 10", |state| state
+            .player_mut(Seat::One)
+            .health = 9));
+        assert!(!fires("health_below 10", |state| state
+            .player_mut(Seat::One)
+            .health = 10));
+    }
+
+    #[test]
+    fn opponent_health_below() {
+        assert!(fires("opponent_health_below 10", |state| state
+            .player_mut(Seat::Two)
+            .health = 9));
+        assert!(!fires("opponent_health_below 10", |state| state
+            .player_mut(Seat::Two)
+            .health = 10));
+    }
+
+    #[test]
+    fn hand_below() {
+        // the card played already left the hand of three it was dealt with
+        assert!(fires("hand_below 3", |_| {}));
+        assert!(!fires("hand_below 3", |state| {
+            state.apply(Seat::One, Action::DrawCard).unwrap();
+        }));
+    }
+
+    #[test]
+    fn ball_power_at_least() {
+        assert!(fires("ball_power 2", |state| state.balls[0].power = 2));
+        assert!(!fires("ball_power 2", |state| state.balls[0].power = 1));
+    }
+
+    #[test]
+    fn graveyard_at_least() {
+        let discard = |state: &mut MatchState| {
+            let player = state.player_mut(Seat::One);
+            let card = player.hand.pop().unwrap();
+            player.graveyard.push(card);
+        };
+        assert!(fires("graveyard 1", discard));
+        assert!(!fires("graveyard 1", |_| {}));
+    }
+
+    #[test]
+    fn rally_at_least() {
+        assert!(fires("rally 2", |state| state.balls[0].rally = 2));
+        assert!(!fires("rally 2", |state| state.balls[0].rally = 1));
+    }
+
+    // the match after the first card with `play: <effect>` was played, and that card
+    fn played(effect: &str) -> (MatchState, CardInstanceId) {
+        let mut state = dealt(&format!("play: {}", effect));
+        let card = play(&mut state);
+        (state, card)
+    }
+
+    fn ball_before() -> BallState {
+        dealt("").balls[0].clone()
+    }
+
+    #[test]
+    fn damage() {
+        let (state, _) = played("damage 2");
+        assert_eq!(opponent_health(&state), PlayerState::DEFAULT_HEALTH - 2);
+    }
+
+    #[test]
+    fn draw() {
+        let (state, _) = played("draw 2");
+        assert_eq!(
+            state.player(Seat::One).hand.len(),
+            MatchState::STARTING_HAND - 1 + 2
+        );
+    }
+
+    #[test]
+    fn buff() {
+        let (state, card) = played("buff health 2");
+        assert_eq!(state.card(card).unwrap().stats().health, 5);
+        // a health buff can kill too
+        let (state, card) = played("buff health -3");
+        assert!(state.player(Seat::One).graveyard.contains(card));
+    }
+
+    #[test]
+    fn spawn_ball() {
+        let (state, _) = played("spawn_ball");
+        assert_eq!(state.balls.len(), 2);
+        assert_eq!(state.balls[1].server, Seat::One);
+    }
+
+    #[test]
+    fn shift() {
+        let (state, card) = played("shift 2");
+        assert_eq!(state.player(Seat::One).battlefield_index(card), Some(2));
+        // there is no slot to the left of the first one
+        let (state, card) = played("shift -1");
+        assert_eq!(state.player(Seat::One).battlefield_index(card), Some(0));
+    }
+
+    #[test]
+    fn ball_speed() {
+        let (state, _) = played("ball_speed 25");
+        assert_eq!(state.table.ball_speed_bonus, 25);
+    }
+
+    #[test]
+    fn recall() {
+        let mut state = dealt("death: recall");
+        let card = play(&mut state);
+        state.destroy(card).unwrap();
+        assert!(state.player(Seat::One).hand_index(card).is_some());
+        assert!(!state.player(Seat::One).graveyard.contains(card));
+    }
+
+    #[test]
+    fn speed_up() {
+        let (state, _) = played("speed_up 50");
+        let speed = ball_before().velocity.length() * 1.5;
+        assert!((state.balls[0].velocity.length() - speed).abs() < 1e-5);
+    }
+
+    #[test]
+    fn deflect() {
+        let (state, _) = played("deflect 90");
+        let before = ball_before().velocity;
+        let turned = Vec2::new(-before.y, before.x);
+        assert!((state.balls[0].velocity - turned).length() < 1e-5);
+    }
+
+    #[test]
+    fn empower() {
+        let (state, _) = played("empower 2");
+        assert_eq!(state.balls[0].power, ball_before().power + 2);
+    }
+
+    #[test]
+    fn paddle_width() {
+        let (state, _) = played("paddle_width 25");
+        assert_eq!(state.paddle(Seat::One).width_bonus, 25);
+        assert_eq!(state.paddle(Seat::Two).width_bonus, 0);
+    }
+
+    #[test]
+    fn paddle_speed() {
+        let (state, _) = played("paddle_speed -10");
+        assert_eq!(state.paddle(Seat::One).speed_bonus, -10);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ability::Stat,
+    ball::BallId,
+    ball_kind::BallKindId,
+    card::{CardId, CardInstanceId},
+    player::{Seat, Zone},
+};
+
+/// Everything a player can ask the rules to do.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    DrawCard,
+    /// play a card from the hand, `slot` is the battlefield slot it goes to or the
+    /// creature it equips. Spells and field cards ignore it.
+    PlayCard {
+        card: CardInstanceId,
+        slot: usize,
+    },
+    /// move a card that is already on the battlefield into another slot
+    MoveCard {
+        card: CardInstanceId,
+        slot: usize,
+    },
+    /// give the match up, the opponent wins
+    Concede,
+}
+
+/// Why a match ended.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    /// the health of the loser ran out
+    Health,
+    /// the loser had no cards left, see [`PlayerState::is_out_of_cards`](crate::PlayerState::is_out_of_cards)
+    DeckOut,
+    Concession,
+    /// the loser left an online match
+    Disconnect,
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndReason::Health => write!(f, "ran out of health"),
+            EndReason::DeckOut => write!(f, "ran out of cards"),
+            EndReason::Concession => write!(f, "conceded"),
+            EndReason::Disconnect => write!(f, "left the match"),
+        }
+    }
+}
+
+/// What happened as the result of an action or a tick.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum MatchEvent {
+    CardDrawn {
+        seat: Seat,
+        card: CardInstanceId,
+    },
+    CardPlayed {
+        seat: Seat,
+        card: CardInstanceId,
+        slot: usize,
+    },
+    /// the spell went to the graveyard, its abilities follow
+    SpellCast {
+        seat: Seat,
+        card: CardInstanceId,
+    },
+    /// the equipment now belongs to `host`, the creature in `slot`
+    CardAttached {
+        seat: Seat,
+        card: CardInstanceId,
+        slot: usize,
+        host: CardInstanceId,
+    },
+    /// replaces the field card `seat` had before, which goes to the graveyard
+    FieldPlayed {
+        seat: Seat,
+        card: CardInstanceId,
+    },
+    /// the deck was shuffled with the match rng, replaying it gives the same order
+    /// and a [view](crate::MatchState::view_for) without the rng forgets the order
+    DeckShuffled {
+        seat: Seat,
+    },
+    /// the card left a zone hidden from whoever gets this event, it is `id`.
+    /// Only [`MatchState::events_for`](crate::MatchState::events_for) adds these
+    CardRevealed {
+        card: CardInstanceId,
+        id: CardId,
+    },
+    /// `ball_speed_bonus` is the new total of the table
+    TableChanged {
+        ball_speed_bonus: isize,
+    },
+    CardMoved {
+        seat: Seat,
+        card: CardInstanceId,
+        from: usize,
+        to: usize,
+    },
+    PlayerHurt {
+        seat: Seat,
+        damage: i32,
+        health: i32,
+    },
+    /// `energy` is what `seat` has now, after playing a card or regenerating
+    EnergyChanged {
+        seat: Seat,
+        energy: u32,
+    },
+    CardBuffed {
+        card: CardInstanceId,
+        stat: Stat,
+        amount: isize,
+    },
+    /// `health` is what the card has left
+    CardDamaged {
+        card: CardInstanceId,
+        damage: isize,
+        health: isize,
+    },
+    /// the card was removed from the battlefield
+    CardDestroyed {
+        seat: Seat,
+        card: CardInstanceId,
+        slot: usize,
+    },
+    /// the card entered the graveyard of `seat`, coming from `from`
+    CardBuried {
+        seat: Seat,
+        card: CardInstanceId,
+        from: Zone,
+    },
+    /// the card was removed from the game, its equipment is buried
+    CardExiled {
+        seat: Seat,
+        card: CardInstanceId,
+        from: Zone,
+    },
+    /// the card came back from the graveyard or the exile into the hand or on top of the deck
+    CardReturned {
+        seat: Seat,
+        card: CardInstanceId,
+        from: Zone,
+        to: Zone,
+    },
+    /// the card acted and waits for its cooldown again
+    CooldownStarted {
+        card: CardInstanceId,
+    },
+    /// a ball of `kind` was served by `seat`, `angle` degrees off straight ahead, counterclockwise
+    BallSpawned {
+        ball: BallId,
+        seat: Seat,
+        kind: BallKindId,
+        angle: i32,
+    },
+    /// the ball got past a player and left the table
+    BallLost {
+        ball: BallId,
+    },
+    /// the ball was hit again in its rally and got faster
+    BallRallied {
+        ball: BallId,
+        rally: u32,
+    },
+    /// the ball got that many percent faster
+    BallSpedUp {
+        ball: BallId,
+        percent: isize,
+    },
+    /// the ball turned by that many degrees, counterclockwise
+    BallDeflected {
+        ball: BallId,
+        degrees: i32,
+    },
+    /// the ball hits that hard now
+    BallEmpowered {
+        ball: BallId,
+        power: u32,
+    },
+    /// the bonuses are the new totals of the paddle of `seat`, in percent
+    PaddleChanged {
+        seat: Seat,
+        width_bonus: isize,
+        speed_bonus: isize,
+    },
+    /// nothing happens in the match after this, `winner` is `None` for a draw
+    MatchEnded {
+        winner: Option<Seat>,
+        reason: EndReason,
+    },
+}
@@ -0,0 +1,234 @@
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball_kind::{BallKind, BallKindId},
+    player::{PlayerState, Seat},
+};
+
+/// Tells the balls of a match apart, never reused within it.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default,
+)]
+pub struct BallId(pub u32);
+
+/// Who serves after a goal, picked by whoever hosts the match.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy::ecs::system::Resource))]
+pub enum ServeRule {
+    /// the player the ball got past
+    #[default]
+    LoserServes,
+    /// the players take turns
+    Alternate,
+}
+
+impl ServeRule {
+    /// `loser_serves` or `alternate`, as the server takes it on the command line.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "loser_serves" => Some(ServeRule::LoserServes),
+            "alternate" => Some(ServeRule::Alternate),
+            _ => None,
+        }
+    }
+}
+
+/// The playing surface, centered on the origin.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Table {
+    pub half_extents: Vec2,
+    /// percent added to the ball speed by field cards
+    pub ball_speed_bonus: isize,
+    pub serve_rule: ServeRule,
+}
+
+impl Default for Table {
+    /// matches the `Battlefield` mesh in `battlefield.glb`
+    fn default() -> Self {
+        Self {
+            half_extents: Vec2::new(2.08, 2.5),
+            ball_speed_bonus: 0,
+            serve_rule: ServeRule::default(),
+        }
+    }
+}
+
+impl Table {
+    /// half the size of a card on the battlefield, what a ball runs into
+    pub const SLOT_HALF_EXTENTS: Vec2 = Vec2::new(25.0 / 60.0, 0.5);
+    /// between the middle of two neighbouring battlefield slots
+    const SLOT_SPACING: f32 = 1.0;
+    /// between the middle line and the battlefield of a player
+    const SLOT_DISTANCE: f32 = 1.2;
+
+    /// How fast the ball crosses this table, never below a tenth of its normal speed.
+    pub fn ball_speed(&self) -> f32 {
+        (100 + self.ball_speed_bonus).max(10) as f32 / 100.0
+    }
+
+    /// The middle of battlefield `slot` of `seat`. The slots run left to right as the player sees them,
+    /// `One` from the bottom edge and `Two` from the top one.
+    pub fn slot_center(&self, seat: Seat, slot: usize) -> Vec2 {
+        let first = (PlayerState::BATTLEFIELD_SIZE - 1) as f32 / 2.0;
+        let center = Vec2::new(
+            (slot as f32 - first) * Self::SLOT_SPACING,
+            -Self::SLOT_DISTANCE,
+        );
+        match seat {
+            Seat::One => center,
+            Seat::Two => -center,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BallState {
+    pub id: BallId,
+    pub kind: BallKindId,
+    pub position: Vec2,
+    /// where the ball heads, also while it waits for the serve
+    pub velocity: Vec2,
+    pub power: u32,
+    /// who served it
+    pub server: Seat,
+    /// seconds before a served ball starts moving
+    pub countdown: f32,
+    /// the hits it took since it was served
+    pub rally: u32,
+    /// copied from its kind along with the power
+    pub radius: f32,
+    pub bounciness: f32,
+}
+
+impl Default for BallState {
+    fn default() -> Self {
+        let kind = BallKind::default();
+        Self {
+            id: BallId::default(),
+            kind: kind.id,
+            position: Vec2::ZERO,
+            velocity: Vec2::new(0.0, 1.0),
+            power: kind.power,
+            server: Seat::One,
+            countdown: 0.0,
+            rally: 0,
+            radius: kind.radius,
+            bounciness: kind.bounciness,
+        }
+    }
+}
+
+impl BallState {
+    /// seconds between a serve and the ball moving
+    pub const SERVE_DELAY: f32 = 3.0;
+    /// the most degrees a serve strays from straight ahead, either way
+    pub const SERVE_SPREAD: i32 = 30;
+    /// percent every hit of a rally adds to the speed
+    pub const RALLY_SPEED_UP: isize = 5;
+    /// no hit or effect makes the ball faster than this
+    pub const MAX_SPEED: f32 = 3.0;
+
+    /// A fresh ball of `kind` in the middle of the table, heading away from `seat` once the countdown is over.
+    pub fn served_by(id: BallId, seat: Seat, kind: &BallKind) -> Self {
+        let direction = match seat {
+            Seat::One => 1.0,
+            Seat::Two => -1.0,
+        };
+        Self {
+            id,
+            kind: kind.id.clone(),
+            velocity: Vec2::new(0.0, direction * kind.speed),
+            power: kind.power,
+            server: seat,
+            countdown: Self::SERVE_DELAY,
+            radius: kind.radius,
+            bounciness: kind.bounciness,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.countdown > 0.0
+    }
+
+    /// Runs the countdown of the serve down. Returns whether the ball started moving just now.
+    pub fn count_down(&mut self, dt: f32) -> bool {
+        if !self.is_waiting() {
+            return false;
+        }
+        self.countdown = (self.countdown - dt).max(0.0);
+        !self.is_waiting()
+    }
+
+    /// Makes the ball `percent` faster, never below a tenth of its speed nor above [`Self::MAX_SPEED`].
+    pub fn speed_up(&mut self, percent: isize) {
+        self.velocity *= (100 + percent).max(10) as f32 / 100.0;
+        self.velocity = self.velocity.clamp_length_max(Self::MAX_SPEED);
+    }
+
+    /// One more hit in the rally, each makes the ball a bit faster.
+    pub fn rally_hit(&mut self) {
+        self.rally += 1;
+        self.speed_up(Self::RALLY_SPEED_UP);
+    }
+
+    /// Turns the ball `degrees` counterclockwise.
+    pub fn deflect(&mut self, degrees: i32) {
+        self.velocity = Vec2::from_angle((degrees as f32).to_radians()).rotate(self.velocity);
+    }
+
+    /// Bounces the ball off a card of `half_extents` around `center` if it ran into it,
+    /// as hard as its kind lets it. Returns whether it did.
+    pub fn bounce_off(&mut self, center: Vec2, half_extents: Vec2) -> bool {
+        let reach = half_extents + Vec2::splat(self.radius);
+        let offset = self.position - center;
+        if self.is_waiting() || offset.x.abs() >= reach.x || offset.y.abs() >= reach.y {
+            return false;
+        }
+        // it came in through the side it is least deep behind, a ball already on its way out is left alone
+        let depth = reach - offset.abs();
+        if depth.x < depth.y {
+            if offset.x * self.velocity.x >= 0.0 {
+                return false;
+            }
+            self.position.x = center.x + reach.x.copysign(offset.x);
+            self.velocity.x = -self.velocity.x;
+        } else {
+            if offset.y * self.velocity.y >= 0.0 {
+                return false;
+            }
+            self.position.y = center.y + reach.y.copysign(offset.y);
+            self.velocity.y = -self.velocity.y;
+        }
+        self.velocity = (self.velocity * self.bounciness).clamp_length_max(Self::MAX_SPEED);
+        true
+    }
+
+    /// Advances the ball and bounces it off the side edges of the table as hard as its kind lets it,
+    /// a served ball waits for its countdown.
+    /// Returns the seat whose edge the ball ran into, the ball stops there.
+    pub fn step(&mut self, dt: f32, table: &Table) -> Option<Seat> {
+        if self.is_waiting() {
+            self.count_down(dt);
+            return None;
+        }
+        self.position += self.velocity * dt;
+        let limit = table.half_extents - Vec2::splat(self.radius);
+        if self.position.x.abs() > limit.x {
+            self.position.x = self.position.x.clamp(-limit.x, limit.x);
+            self.velocity.x = -self.velocity.x;
+            self.velocity = (self.velocity * self.bounciness).clamp_length_max(Self::MAX_SPEED);
+        }
+        if self.position.y.abs() > limit.y {
+            self.position.y = self.position.y.clamp(-limit.y, limit.y);
+            self.velocity = Vec2::ZERO;
+            return Some(if self.position.y < 0.0 {
+                Seat::One
+            } else {
+                Seat::Two
+            });
+        }
+        None
+    }
+}
@@ -0,0 +1,268 @@
+//! The kinds of ball a match can put into play, listed in `balls.ron`:
+//!
+//! ```text
+//! [
+//!     (id: "standard", name: "ball", radius: 0.1, power: 1, color: (40, 90, 255)),
+//!     (id: "heavy", name: "heavy ball", radius: 0.15, power: 3, bounciness: 0.9, speed: 0.7, color: (90, 90, 90)),
+//! ]
+//! ```
+//!
+//! Cards put one into play with `spawn_ball <id>`, a plain `spawn_ball` and every serve use `standard`.
+
+use std::{collections::BTreeMap, fmt, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::BallKindError;
+
+const RADIUS: RangeInclusive<f32> = 0.05..=0.3;
+const POWER: RangeInclusive<f32> = 0.0..=10.0;
+const BOUNCINESS: RangeInclusive<f32> = 0.5..=1.5;
+const SPEED: RangeInclusive<f32> = 0.25..=3.0;
+
+/// Stable identifier of a ball kind, like `heavy`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct BallKindId(pub String);
+
+impl BallKindId {
+    pub const STANDARD: &'static str = "standard";
+
+    /// The kind every serve uses, there always is one.
+    pub fn standard() -> Self {
+        Self(Self::STANDARD.to_string())
+    }
+
+    /// Lowercase ascii letters, digits and `_`, like card ids.
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_empty()
+            && self
+                .0
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+}
+
+impl Default for BallKindId {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl fmt::Display for BallKindId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for BallKindId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// One entry of `balls.ron`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BallKind {
+    pub id: BallKindId,
+    pub name: String,
+    pub radius: f32,
+    /// what it hits for when it is served
+    pub power: u32,
+    /// how much of its speed it keeps bouncing off an edge, above 1 it gains some
+    #[serde(default = "one")]
+    pub bounciness: f32,
+    /// how fast it is served, compared to the standard ball
+    #[serde(default = "one")]
+    pub speed: f32,
+    pub color: (u8, u8, u8),
+}
+
+impl Default for BallKind {
+    /// the ball the game had before there were kinds
+    fn default() -> Self {
+        Self {
+            id: BallKindId::standard(),
+            name: "ball".to_string(),
+            radius: 0.1,
+            power: 1,
+            bounciness: 1.0,
+            speed: 1.0,
+            color: (0, 0, 255),
+        }
+    }
+}
+
+impl BallKind {
+    fn validate(&self) -> Result<(), BallKindError> {
+        if !self.id.is_valid() {
+            return Err(BallKindError::BadId(self.id.clone()));
+        }
+        for (field, value, range) in [
+            ("radius", self.radius, RADIUS),
+            ("power", self.power as f32, POWER),
+            ("bounciness", self.bounciness, BOUNCINESS),
+            ("speed", self.speed, SPEED),
+        ] {
+            if !range.contains(&value) {
+                return Err(BallKindError::OutOfRange {
+                    id: self.id.clone(),
+                    field,
+                    value,
+                    min: *range.start(),
+                    max: *range.end(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every ball kind of the game by id. The standard kind is always there, built in if the file lacks it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "bevy",
+    derive(
+        bevy::asset::Asset,
+        bevy::reflect::TypePath,
+        bevy::ecs::system::Resource
+    )
+)]
+pub struct BallKinds {
+    kinds: BTreeMap<BallKindId, BallKind>,
+}
+
+impl Default for BallKinds {
+    fn default() -> Self {
+        let standard = BallKind::default();
+        Self {
+            kinds: BTreeMap::from([(standard.id.clone(), standard)]),
+        }
+    }
+}
+
+impl BallKinds {
+    /// Reads and checks `balls.ron`, stopping at the first problem.
+    pub fn parse(bytes: &[u8]) -> Result<Self, BallKindError> {
+        let list: Vec<BallKind> =
+            ron::de::from_bytes(bytes).map_err(|err| BallKindError::Parse {
+                line: err.position.line,
+                message: err.code.to_string(),
+            })?;
+        let mut kinds = Self::default();
+        let mut seen = Vec::new();
+        for kind in list {
+            kind.validate()?;
+            if seen.contains(&kind.id) {
+                return Err(BallKindError::Duplicate(kind.id));
+            }
+            seen.push(kind.id.clone());
+            kinds.kinds.insert(kind.id.clone(), kind);
+        }
+        Ok(kinds)
+    }
+
+    /// The kind with that id, the standard one for an id nobody defined.
+    pub fn get(&self, id: &BallKindId) -> &BallKind {
+        self.kinds
+            .get(id)
+            .or_else(|| self.kinds.get(&BallKindId::standard()))
+            .expect("the standard kind is always there")
+    }
+
+    pub fn contains(&self, id: &BallKindId) -> bool {
+        self.kinds.contains_key(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &BallKind> {
+        self.kinds.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_kinds() {
+        let kinds = BallKinds::parse(include_bytes!("../../../assets/balls.ron")).unwrap();
+        for id in ["standard", "heavy", "feather"] {
+            assert!(kinds.contains(&BallKindId::from(id)), "{}", id);
+        }
+        let heavy = kinds.get(&BallKindId::from("heavy"));
+        assert_eq!(heavy.power, 3);
+        assert_eq!(heavy.bounciness, 0.9);
+        // a kind nobody defined serves as the standard one
+        let unknown = kinds.get(&BallKindId::from("golden"));
+        assert_eq!(unknown.id, BallKindId::standard());
+    }
+
+    #[test]
+    fn out_of_range() {
+        let parsed = BallKinds::parse(
+            b"[(id: \"huge\", name: \"huge ball\", radius: 0.5, power: 1, color: (0, 0, 0))]",
+        );
+        assert_eq!(
+            parsed,
+            Err(BallKindError::OutOfRange {
+                id: BallKindId::from("huge"),
+                field: "radius",
+                value: 0.5,
+                min: *RADIUS.start(),
+                max: *RADIUS.end(),
+            })
+        );
+        let parsed = BallKinds::parse(
+            b"[(id: \"dead\", name: \"dead ball\", radius: 0.1, power: 1, bounciness: 0.2, \
+              color: (0, 0, 0))]",
+        );
+        assert_eq!(
+            parsed,
+            Err(BallKindError::OutOfRange {
+                id: BallKindId::from("dead"),
+                field: "bounciness",
+                value: 0.2,
+                min: *BOUNCINESS.start(),
+                max: *BOUNCINESS.end(),
+            })
+        );
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let parsed = BallKinds::parse(
+            b"[
+                (id: \"heavy\", name: \"heavy ball\", radius: 0.15, power: 3, color: (0, 0, 0)),
+                (id: \"heavy\", name: \"heavier ball\", radius: 0.2, power: 4, color: (0, 0, 0)),
+            ]",
+        );
+        assert_eq!(
+            parsed,
+            Err(BallKindError::Duplicate(BallKindId::from("heavy")))
+        );
+    }
+
+    #[test]
+    fn standard_built_in() {
+        let kinds = BallKinds::parse(
+            b"[(id: \"heavy\", name: \"heavy ball\", radius: 0.15, power: 3, color: (0, 0, 0))]",
+        )
+        .unwrap();
+        assert_eq!(kinds.len(), 2);
+        assert_eq!(kinds.get(&BallKindId::standard()), &BallKind::default());
+        assert_eq!(BallKinds::parse(b"[]").unwrap(), BallKinds::default());
+    }
+}
@@ -0,0 +1,503 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ability::{Abilities, Stat},
+    error::AbilityError,
+    player::Seat,
+};
+
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CardType {
+    Creature,
+    /// resolves its `play` abilities, then goes to the graveyard
+    Spell,
+    /// attaches to a creature on the battlefield and adds its attack and health to it
+    Equipment,
+    /// one per player next to the battlefield, changes the table while it stays
+    Field,
+}
+
+/// The numbers printed on a card. `attack` and `health` only matter for creatures.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CardStats {
+    pub cost: isize,
+    pub cooldown: isize,
+    pub attack: isize,
+    pub health: isize,
+}
+
+impl CardStats {
+    pub fn get(&self, stat: Stat) -> isize {
+        match stat {
+            Stat::Cost => self.cost,
+            Stat::Cooldown => self.cooldown,
+            Stat::Attack => self.attack,
+            Stat::Health => self.health,
+        }
+    }
+
+    /// The stats with `buff` applied, no stat goes below zero.
+    pub fn with(mut self, buff: Buff) -> Self {
+        let value = (self.get(buff.stat) + buff.amount).max(0);
+        match buff.stat {
+            Stat::Cost => self.cost = value,
+            Stat::Cooldown => self.cooldown = value,
+            Stat::Attack => self.attack = value,
+            Stat::Health => self.health = value,
+        }
+        self
+    }
+}
+
+/// A change to one stat of a card in play, kept around so it can be reverted.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Buff {
+    pub stat: Stat,
+    pub amount: isize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ISizeWithMax {
+    pub current: isize,
+    pub max: isize,
+}
+
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Keyword {
+    /// ready to act as soon as it is played
+    Swift,
+    /// takes one less damage from every hit
+    Armored,
+    /// can't be moved once played
+    Rooted,
+}
+
+impl FromStr for Keyword {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "swift" => Ok(Keyword::Swift),
+            "armored" => Ok(Keyword::Armored),
+            "rooted" => Ok(Keyword::Rooted),
+            _ => Err(AbilityError::UnknownKeyword(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Keyword::Swift => write!(f, "swift"),
+            Keyword::Armored => write!(f, "armored"),
+            Keyword::Rooted => write!(f, "rooted"),
+        }
+    }
+}
+
+/// The keywords of a card, written space separated in `cards.csv`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Keywords(pub Vec<Keyword>);
+
+impl Keywords {
+    pub fn contains(&self, keyword: Keyword) -> bool {
+        self.0.contains(&keyword)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Keyword> {
+        self.0.iter()
+    }
+}
+
+impl FromStr for Keywords {
+    type Err = AbilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Keywords)
+    }
+}
+
+impl TryFrom<String> for Keywords {
+    type Error = AbilityError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Keywords> for String {
+    fn from(keywords: Keywords) -> Self {
+        keywords.to_string()
+    }
+}
+
+impl fmt::Display for Keywords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = self.0.iter().map(Keyword::to_string).collect();
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// Stable identifier of a card definition, like `human_soldier`.
+/// Decks, saves and network messages refer to cards by it, the name is only shown.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[serde(transparent)]
+pub struct CardId(pub String);
+
+impl CardId {
+    /// Lowercase ascii letters, digits and `_`, so it is safe in file names and decklists.
+    pub fn is_valid(&self) -> bool {
+        !self.0.is_empty()
+            && self
+                .0
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
+
+    /// Stands for a card the player may not see, see [`MatchState::view_for`](crate::MatchState::view_for).
+    /// Never valid, so no definition has it.
+    pub fn hidden() -> Self {
+        Self(String::new())
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for CardId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<&str> for CardId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+/// Immutable card template, one per row of `cards.csv`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "CardRow", into = "CardRow")]
+pub struct CardInfo {
+    pub id: CardId,
+    pub name: String,
+    pub name_zh: String,
+    pub desc: String,
+    pub card_type: CardType,
+    pub stats: CardStats,
+    pub keywords: Keywords,
+    pub abilities: Abilities,
+    /// portrait image, relative to the assets folder
+    pub art: String,
+}
+
+// the stats have their own columns in `cards.csv`. Spelled out instead of
+// `#[serde(flatten)]`, which bincode can't serialize.
+#[derive(Serialize, Deserialize)]
+struct CardRow {
+    id: CardId,
+    name: String,
+    name_zh: String,
+    desc: String,
+    card_type: CardType,
+    cost: isize,
+    cooldown: isize,
+    #[serde(default)]
+    attack: isize,
+    #[serde(default)]
+    health: isize,
+    #[serde(default)]
+    keywords: Keywords,
+    #[serde(default)]
+    abilities: Abilities,
+    art: String,
+}
+
+impl CardInfo {
+    /// Stands in for the definition of `id` until it is looked up in a
+    /// [`CardRegistry`](crate::CardRegistry), see [`CardInstance::info`].
+    pub(crate) fn unlinked(id: CardId) -> Self {
+        Self {
+            id,
+            name: String::new(),
+            name_zh: String::new(),
+            desc: String::new(),
+            card_type: CardType::Creature,
+            stats: CardStats::default(),
+            keywords: Keywords::default(),
+            abilities: Abilities::default(),
+            art: String::new(),
+        }
+    }
+}
+
+impl From<CardRow> for CardInfo {
+    fn from(row: CardRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            name_zh: row.name_zh,
+            desc: row.desc,
+            card_type: row.card_type,
+            stats: CardStats {
+                cost: row.cost,
+                cooldown: row.cooldown,
+                attack: row.attack,
+                health: row.health,
+            },
+            keywords: row.keywords,
+            abilities: row.abilities,
+            art: row.art,
+        }
+    }
+}
+
+impl From<CardInfo> for CardRow {
+    fn from(info: CardInfo) -> Self {
+        Self {
+            id: info.id,
+            name: info.name,
+            name_zh: info.name_zh,
+            desc: info.desc,
+            card_type: info.card_type,
+            cost: info.stats.cost,
+            cooldown: info.stats.cooldown,
+            attack: info.stats.attack,
+            health: info.stats.health,
+            keywords: info.keywords,
+            abilities: info.abilities,
+            art: info.art,
+        }
+    }
+}
+
+/// Identifies one physical copy of a card for the whole match.
+#[derive(
+    Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Serialize, Deserialize,
+)]
+pub struct CardInstanceId(pub u32);
+
+/// A card in the match. `info` never changes, everything that happens to the card is kept next to it.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CardInstance {
+    pub id: CardInstanceId,
+    pub owner: Seat,
+    /// only its id is serialized, [`MatchState::link`](crate::MatchState::link) puts the rest back
+    #[serde(with = "by_id")]
+    pub info: CardInfo,
+    /// applied in order on top of `info.stats`
+    pub buffs: Vec<Buff>,
+    /// equipment carried by this creature
+    pub attached: Vec<CardInstance>,
+    /// damage taken, health is what is left of the buffed maximum
+    pub damage: isize,
+    /// seconds left before the card may act again once it is on the battlefield
+    pub cooldown: f32,
+    /// seconds spent on the battlefield, drives `tick` abilities
+    pub elapsed: f32,
+}
+
+impl CardInstance {
+    pub fn new(id: CardInstanceId, owner: Seat, info: CardInfo) -> Self {
+        Self {
+            id,
+            owner,
+            info,
+            buffs: Vec::new(),
+            attached: Vec::new(),
+            damage: 0,
+            cooldown: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Forgets everything about the card but which copy it is, as an opponent sees a card in hand.
+    pub fn hide(&mut self) {
+        *self = Self::new(self.id, self.owner, CardInfo::unlinked(CardId::hidden()));
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.info.id.is_hidden()
+    }
+
+    /// The current stats, buffs and equipment included.
+    pub fn stats(&self) -> CardStats {
+        let stats = self
+            .buffs
+            .iter()
+            .fold(self.info.stats, |stats, buff| stats.with(*buff));
+        self.attached.iter().fold(stats, |stats, equipment| {
+            let bonus = equipment.stats();
+            stats
+                .with(Buff {
+                    stat: Stat::Attack,
+                    amount: bonus.attack,
+                })
+                .with(Buff {
+                    stat: Stat::Health,
+                    amount: bonus.health,
+                })
+        })
+    }
+
+    pub fn health(&self) -> ISizeWithMax {
+        let max = self.stats().health;
+        ISizeWithMax {
+            current: (max - self.damage).max(0),
+            max,
+        }
+    }
+
+    pub fn has_keyword(&self, keyword: Keyword) -> bool {
+        self.info.keywords.contains(keyword)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.info.card_type == CardType::Creature && self.health().current <= 0
+    }
+
+    pub fn buff(&mut self, buff: Buff) {
+        self.buffs.push(buff);
+    }
+
+    /// Removes one earlier `buff`, returns whether there was one.
+    pub fn revert(&mut self, buff: Buff) -> bool {
+        match self.buffs.iter().rposition(|applied| *applied == buff) {
+            Some(index) => {
+                self.buffs.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Takes a hit, returns the damage actually taken.
+    pub fn take_damage(&mut self, amount: isize) -> isize {
+        let armor = if self.has_keyword(Keyword::Armored) {
+            1
+        } else {
+            0
+        };
+        let taken = (amount - armor).max(0);
+        self.damage += taken;
+        taken
+    }
+
+    pub fn heal(&mut self, amount: isize) {
+        self.damage = (self.damage - amount).max(0);
+    }
+
+    /// Back to the printed card, e.g. when it leaves the battlefield. Returns the equipment it carried.
+    pub fn reset(&mut self) -> Vec<CardInstance> {
+        self.buffs.clear();
+        self.damage = 0;
+        self.cooldown = 0.0;
+        self.elapsed = 0.0;
+        std::mem::take(&mut self.attached)
+    }
+
+    /// Puts the card on the battlefield: it has to cool down before acting.
+    pub fn enter_battlefield(&mut self) {
+        self.cooldown = if self.has_keyword(Keyword::Swift) {
+            0.0
+        } else {
+            self.stats().cooldown as f32
+        };
+        self.elapsed = 0.0;
+    }
+
+    /// The card acted, it has to wait its full cooldown again.
+    pub fn restart_cooldown(&mut self) {
+        self.cooldown = self.stats().cooldown as f32;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown <= 0.0
+    }
+}
+
+mod by_id {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{CardId, CardInfo};
+
+    pub fn serialize<S: Serializer>(info: &CardInfo, serializer: S) -> Result<S::Ok, S::Error> {
+        info.id.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CardInfo, D::Error> {
+        CardId::deserialize(deserializer).map(CardInfo::unlinked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn soldier() -> CardInstance {
+        let registry =
+            testing::registry("soldier,soldier,士兵,creature,1,1,2,4,,soldier,,soldier.png\n");
+        let info = registry.get(&"soldier".into()).unwrap().clone();
+        CardInstance::new(CardInstanceId(1), Seat::One, info)
+    }
+
+    #[test]
+    fn revert() {
+        let mut card = soldier();
+        let buff = Buff {
+            stat: Stat::Attack,
+            amount: 3,
+        };
+        card.buff(buff);
+        card.buff(buff);
+        assert_eq!(card.stats().attack, 8);
+        assert!(card.revert(buff));
+        assert_eq!(card.stats().attack, 5);
+        assert!(card.revert(buff));
+        assert_eq!(card.stats().attack, 2);
+        assert!(!card.revert(buff));
+        assert_eq!(card.stats(), card.info.stats);
+    }
+
+    #[test]
+    fn revert_keeps_other_buffs() {
+        let mut card = soldier();
+        let weaken = Buff {
+            stat: Stat::Attack,
+            amount: -5,
+        };
+        let strengthen = Buff {
+            stat: Stat::Attack,
+            amount: 4,
+        };
+        card.buff(weaken);
+        card.buff(strengthen);
+        // the stat stopped at zero before the second buff
+        assert_eq!(card.stats().attack, 4);
+        assert!(card.revert(weaken));
+        assert_eq!(card.buffs, vec![strengthen]);
+        assert_eq!(card.stats().attack, 6);
+    }
+
+    #[test]
+    fn heal() {
+        let mut card = soldier();
+        assert_eq!(card.take_damage(3), 3);
+        card.heal(2);
+        assert_eq!(card.health().current, 3);
+        // never past the maximum
+        card.heal(10);
+        assert_eq!(card.damage, 0);
+        assert_eq!(card.health().current, 4);
+    }
+}
@@ -0,0 +1,48 @@
+//! Fingerprints of the simulation. The server sends the [`MatchState::checksum`](crate::MatchState::checksum)
+//! of every tick along with its snapshot, a client that ran the same tick to a different sum has drifted apart.
+
+use glam::Vec2;
+
+/// FNV-1a over little-endian bytes: defined down to the bit on every platform,
+/// unlike the hashers of `std` that may change between versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checksum(u64);
+
+impl Checksum {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET)
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Exact bits, so `0.0` and `-0.0` differ like they can in a simulation that drifted.
+    pub fn f32(&mut self, value: f32) -> &mut Self {
+        self.bytes(&value.to_bits().to_le_bytes())
+    }
+
+    pub fn vec2(&mut self, value: Vec2) -> &mut Self {
+        self.f32(value.x).f32(value.y)
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
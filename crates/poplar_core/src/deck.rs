@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{CardInstance, CardInstanceId},
+    rng::MatchRng,
+};
+
+/// A player's library. The top of the deck is the end of the vec.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Deck {
+    cards: Vec<CardInstance>,
+}
+
+impl Deck {
+    pub fn new(cards: Vec<CardInstance>) -> Self {
+        Self { cards }
+    }
+
+    pub fn shuffle(&mut self, rng: &mut MatchRng) {
+        rng.shuffle(&mut self.cards);
+    }
+
+    /// Forgets the cards and their order, as a player sees a deck they can't look into.
+    pub fn hide(&mut self) {
+        self.cards.iter_mut().for_each(CardInstance::hide);
+        self.cards.sort_by_key(|card| card.id);
+    }
+
+    pub fn draw_top(&mut self) -> Option<CardInstance> {
+        self.cards.pop()
+    }
+
+    pub fn draw_bottom(&mut self) -> Option<CardInstance> {
+        if self.cards.is_empty() {
+            return None;
+        }
+        Some(self.cards.remove(0))
+    }
+
+    /// Up to `count` cards from the top down, without taking them.
+    pub fn peek(&self, count: usize) -> impl Iterator<Item = &CardInstance> {
+        self.cards.iter().rev().take(count)
+    }
+
+    /// The cards `matches` accepts, from the top down.
+    pub fn search<'a>(
+        &'a self,
+        mut matches: impl FnMut(&CardInstance) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a CardInstance> {
+        self.cards.iter().rev().filter(move |card| matches(card))
+    }
+
+    /// Puts `card` under the `from_top` topmost cards, `0` is the top, anything past the bottom is the bottom.
+    pub fn insert(&mut self, from_top: usize, card: CardInstance) {
+        let index = self.cards.len().saturating_sub(from_top);
+        self.cards.insert(index, card);
+    }
+
+    /// Takes a given card out of the deck, wherever it is.
+    pub fn remove(&mut self, id: CardInstanceId) -> Option<CardInstance> {
+        let index = self.cards.iter().position(|card| card.id == id)?;
+        Some(self.cards.remove(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CardInstance> {
+        self.cards.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut CardInstance> {
+        self.cards.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, CardId, CardRegistry, MatchState, Seat};
+
+    const CARDS: &str = "\
+soldier,soldier,士兵,creature,1,1,1,3,,soldier,,soldier.png
+archer,archer,弓箭手,creature,2,1,2,2,,archer,,archer.png
+";
+
+    fn registry() -> CardRegistry {
+        testing::registry(CARDS)
+    }
+
+    fn order(state: &MatchState, seat: Seat) -> Vec<CardInstanceId> {
+        state.player(seat).deck.iter().map(|card| card.id).collect()
+    }
+
+    // cards 1 to `count` from the bottom up, every other one an archer
+    fn numbered(count: u32) -> Deck {
+        let registry = registry();
+        let cards = (1..=count)
+            .map(|id| {
+                let card = if id % 2 == 0 { "archer" } else { "soldier" };
+                let info = registry.get(&card.into()).unwrap().clone();
+                CardInstance::new(CardInstanceId(id), Seat::One, info)
+            })
+            .collect();
+        Deck::new(cards)
+    }
+
+    fn ids<'a>(cards: impl Iterator<Item = &'a CardInstance>) -> Vec<u32> {
+        cards.map(|card| card.id.0).collect()
+    }
+
+    #[test]
+    fn same_seed_same_order() {
+        let registry = registry();
+        let deck = [["soldier", "archer"]; 10].concat();
+        let first = testing::match_state(&registry, &deck, 99);
+        let second = testing::match_state(&registry, &deck, 99);
+        for seat in Seat::ALL {
+            assert_eq!(order(&first, seat), order(&second, seat));
+        }
+        let other = testing::match_state(&registry, &deck, 100);
+        assert_ne!(order(&first, Seat::One), order(&other, Seat::One));
+    }
+
+    #[test]
+    fn shuffle_keeps_the_cards() {
+        let mut deck = numbered(20);
+        deck.shuffle(&mut MatchRng::new(5));
+        let mut shuffled = ids(deck.iter());
+        assert_ne!(shuffled, (1..=20).collect::<Vec<_>>());
+        shuffled.sort();
+        assert_eq!(shuffled, (1..=20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn draw_top_and_bottom() {
+        let mut deck = numbered(3);
+        assert_eq!(deck.draw_top().map(|card| card.id.0), Some(3));
+        assert_eq!(deck.draw_bottom().map(|card| card.id.0), Some(1));
+        assert_eq!(deck.draw_bottom().map(|card| card.id.0), Some(2));
+        assert!(deck.draw_bottom().is_none());
+        assert!(deck.draw_top().is_none());
+    }
+
+    #[test]
+    fn peek() {
+        let deck = numbered(5);
+        assert_eq!(ids(deck.peek(2)), vec![5, 4]);
+        assert_eq!(ids(deck.peek(10)), vec![5, 4, 3, 2, 1]);
+        assert_eq!(deck.len(), 5);
+    }
+
+    #[test]
+    fn search() {
+        let deck = numbered(5);
+        let archers = deck.search(|card| card.info.id == CardId::from("archer"));
+        assert_eq!(ids(archers), vec![4, 2]);
+        assert_eq!(deck.search(|card| card.info.stats.cost > 5).count(), 0);
+    }
+
+    #[test]
+    fn insert() {
+        let mut deck = numbered(3);
+        let mut extra = numbered(6);
+        deck.insert(0, extra.draw_top().unwrap());
+        deck.insert(2, extra.draw_top().unwrap());
+        deck.insert(100, extra.draw_top().unwrap());
+        assert_eq!(ids(deck.peek(6)), vec![6, 3, 5, 2, 1, 4]);
+    }
+}
@@ -0,0 +1,295 @@
+//! Decklists as the players build and save them.
+//!
+//! A decklist is saved as RON, the game keeps them as `decks/<name>.ron`.
+//! `cards` maps card ids to their number of copies:
+//!
+//! ```text
+//! (
+//!     name: "undead",
+//!     cards: {
+//!         "human_soldier": 3,
+//!         "zombie": 3,
+//!     },
+//! )
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{card::CardId, error::DeckError, registry::CardRegistry};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Decklist {
+    pub name: String,
+    pub cards: BTreeMap<CardId, usize>,
+}
+
+impl Decklist {
+    pub const SIZE: usize = 12;
+    pub const MAX_COPIES: usize = 3;
+
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cards: BTreeMap::new(),
+        }
+    }
+
+    /// Up to [`MAX_COPIES`](Self::MAX_COPIES) of every card of the registry in turn until the deck is full,
+    /// for players without a deck of their own.
+    pub fn starter(registry: &CardRegistry) -> Self {
+        let mut deck = Self::new("starter");
+        for _ in 0..Self::MAX_COPIES {
+            for id in registry.ids() {
+                if deck.len() < Self::SIZE {
+                    *deck.cards.entry(id.clone()).or_default() += 1;
+                }
+            }
+        }
+        deck
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn copies(&self, card: &CardId) -> usize {
+        self.cards.get(card).copied().unwrap_or_default()
+    }
+
+    /// Adds one copy of `card` if neither the copy limit nor the deck size is reached.
+    pub fn add(&mut self, card: &CardId) -> Result<(), DeckError> {
+        if self.copies(card) >= Self::MAX_COPIES {
+            return Err(DeckError::TooManyCopies {
+                card: card.clone(),
+                max: Self::MAX_COPIES,
+            });
+        }
+        if self.len() >= Self::SIZE {
+            return Err(DeckError::WrongSize {
+                expected: Self::SIZE,
+                actual: self.len() + 1,
+            });
+        }
+        *self.cards.entry(card.clone()).or_default() += 1;
+        Ok(())
+    }
+
+    /// Removes one copy of `card`, returns whether there was one.
+    pub fn remove(&mut self, card: &CardId) -> bool {
+        match self.cards.get_mut(card) {
+            Some(copies) if *copies > 1 => *copies -= 1,
+            Some(_) => {
+                self.cards.remove(card);
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Whether the deck can be saved and played with the cards of `registry`.
+    pub fn check(&self, registry: &CardRegistry) -> Result<(), DeckError> {
+        let name_ok = !self.name.trim().is_empty()
+            && self
+                .name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_');
+        if !name_ok {
+            return Err(DeckError::BadName(self.name.clone()));
+        }
+        for (card, copies) in self.cards.iter() {
+            registry.get(card)?;
+            if *copies > Self::MAX_COPIES {
+                return Err(DeckError::TooManyCopies {
+                    card: card.clone(),
+                    max: Self::MAX_COPIES,
+                });
+            }
+        }
+        if self.len() != Self::SIZE {
+            return Err(DeckError::WrongSize {
+                expected: Self::SIZE,
+                actual: self.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Every copy in id order, as [`MatchState::new`](crate::MatchState::new) takes it.
+    pub fn card_ids(&self) -> Vec<CardId> {
+        self.cards
+            .iter()
+            .flat_map(|(card, copies)| (0..*copies).map(move |_| card.clone()))
+            .collect()
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, DeckError> {
+        ron::from_str(text).map_err(|err| DeckError::Malformed(err.to_string()))
+    }
+
+    pub fn to_ron(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("decklists always serialize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::UnknownCard, testing};
+
+    const CARDS: &str = "\
+ant,ant,蚁,creature,1,1,1,1,,ant,,ant.png
+bat,bat,蝠,creature,1,1,1,1,,bat,,bat.png
+cat,cat,猫,creature,1,1,1,1,,cat,,cat.png
+dog,dog,狗,creature,1,1,1,1,,dog,,dog.png
+eel,eel,鳗,creature,1,1,1,1,,eel,,eel.png
+";
+
+    fn id(id: &str) -> CardId {
+        CardId::from(id)
+    }
+
+    #[test]
+    fn copy_limit() {
+        let mut deck = Decklist::new("ants");
+        for _ in 0..Decklist::MAX_COPIES {
+            deck.add(&id("ant")).unwrap();
+        }
+        assert_eq!(
+            deck.add(&id("ant")),
+            Err(DeckError::TooManyCopies {
+                card: id("ant"),
+                max: Decklist::MAX_COPIES
+            })
+        );
+        assert_eq!(deck.copies(&id("ant")), Decklist::MAX_COPIES);
+
+        let registry = testing::registry(CARDS);
+        let mut deck = Decklist::starter(&registry);
+        deck.cards.insert(id("ant"), Decklist::MAX_COPIES + 1);
+        deck.remove(&id("bat"));
+        assert_eq!(
+            deck.check(&registry),
+            Err(DeckError::TooManyCopies {
+                card: id("ant"),
+                max: Decklist::MAX_COPIES
+            })
+        );
+    }
+
+    #[test]
+    fn deck_size() {
+        let registry = testing::registry(CARDS);
+        let mut deck = Decklist::starter(&registry);
+        assert_eq!(
+            deck.add(&id("eel")),
+            Err(DeckError::WrongSize {
+                expected: Decklist::SIZE,
+                actual: Decklist::SIZE + 1
+            })
+        );
+        assert!(deck.remove(&id("eel")));
+        assert_eq!(
+            deck.check(&registry),
+            Err(DeckError::WrongSize {
+                expected: Decklist::SIZE,
+                actual: Decklist::SIZE - 1
+            })
+        );
+        deck.add(&id("eel")).unwrap();
+        assert_eq!(deck.check(&registry), Ok(()));
+    }
+
+    #[test]
+    fn remove() {
+        let mut deck = Decklist::new("bats");
+        deck.add(&id("bat")).unwrap();
+        deck.add(&id("bat")).unwrap();
+        assert!(deck.remove(&id("bat")));
+        assert_eq!(deck.copies(&id("bat")), 1);
+        assert!(deck.remove(&id("bat")));
+        assert!(!deck.cards.contains_key(&id("bat")));
+        assert!(!deck.remove(&id("bat")));
+        assert!(deck.is_empty());
+    }
+
+    #[test]
+    fn unknown_card() {
+        let registry = testing::registry(CARDS);
+        let mut deck = Decklist::starter(&registry);
+        deck.remove(&id("ant"));
+        deck.cards.insert(id("fox"), 1);
+        assert_eq!(
+            deck.check(&registry),
+            Err(DeckError::UnknownCard(UnknownCard(id("fox"))))
+        );
+    }
+
+    #[test]
+    fn bad_name() {
+        let registry = testing::registry(CARDS);
+        let mut deck = Decklist::starter(&registry);
+        // the name becomes the file name of the deck
+        for name in [
+            "",
+            "   ",
+            "../starter",
+            "a/b",
+            "c:\\d",
+            "deck.ron",
+            "tab\tbed",
+        ] {
+            deck.name = name.to_string();
+            assert_eq!(
+                deck.check(&registry),
+                Err(DeckError::BadName(name.to_string()))
+            );
+        }
+        for name in ["my deck", "fast-ants_2", "亡灵"] {
+            deck.name = name.to_string();
+            assert_eq!(deck.check(&registry), Ok(()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn starter() {
+        let registry = testing::registry(CARDS);
+        let deck = Decklist::starter(&registry);
+        assert_eq!(deck.name, "starter");
+        assert_eq!(deck.check(&registry), Ok(()));
+        // every card once, then again, until the deck is full
+        let copies = ["ant", "bat", "cat", "dog", "eel"].map(|card| deck.copies(&id(card)));
+        assert_eq!(copies, [3, 3, 2, 2, 2]);
+    }
+
+    #[test]
+    fn card_ids() {
+        let mut deck = Decklist::new("pets");
+        deck.add(&id("dog")).unwrap();
+        deck.add(&id("cat")).unwrap();
+        deck.add(&id("dog")).unwrap();
+        assert_eq!(deck.card_ids(), [id("cat"), id("dog"), id("dog")]);
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let registry = testing::registry(CARDS);
+        let deck = Decklist::starter(&registry);
+        assert_eq!(Decklist::from_ron(&deck.to_ron()), Ok(deck));
+
+        let saved = "(name: \"undead\", cards: {\"ant\": 3, \"bat\": 1})";
+        let deck = Decklist::from_ron(saved).unwrap();
+        assert_eq!(deck.name, "undead");
+        assert_eq!(deck.len(), 4);
+        assert!(matches!(
+            Decklist::from_ron("(name: \"undead\")"),
+            Err(DeckError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::{
+    ball_kind::BallKindId,
+    card::{CardId, CardInstanceId},
+    player::{Seat, Zone},
+};
+
+/// Why an [`Action`](crate::Action) was refused. The state is left untouched.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    #[error("the deck of {0:?} is empty")]
+    DeckEmpty(Seat),
+    #[error("the hand of {0:?} is full")]
+    HandFull(Seat),
+    #[error("card {0:?} is not in the hand of the acting player")]
+    NotInHand(CardInstanceId),
+    #[error("card {0:?} is not on the battlefield of the acting player")]
+    NotOnBattlefield(CardInstanceId),
+    #[error("slot {0} does not exist")]
+    NoSuchSlot(usize),
+    #[error("slot {0} is already occupied")]
+    SlotOccupied(usize),
+    #[error("card {0:?} is still on cooldown")]
+    OnCooldown(CardInstanceId),
+    #[error("card {0:?} is rooted to its slot")]
+    Rooted(CardInstanceId),
+    #[error("there is no creature in slot {0} to equip")]
+    NothingToEquip(usize),
+    #[error("card {0:?} is not in the match")]
+    NoSuchCard(CardInstanceId),
+    #[error("card {0:?} is neither in the graveyard nor in the exile")]
+    NotInPile(CardInstanceId),
+    #[error("cards can only return to the hand or the deck, not the {0}")]
+    CantReturnTo(Zone),
+    #[error("the card costs {cost} energy but only {energy} is left")]
+    NotEnoughEnergy { cost: u32, energy: u32 },
+    #[error("the match is over")]
+    MatchOver,
+}
+
+/// Why the `abilities` or `keywords` of a card could not be read.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AbilityError {
+    #[error("`{0}` has no trigger, expected `<trigger>: <effect>`")]
+    MissingTrigger(String),
+    #[error("unknown trigger `{0}`")]
+    UnknownTrigger(String),
+    #[error("unknown effect `{0}`")]
+    UnknownEffect(String),
+    #[error("unknown condition `{0}`")]
+    UnknownCondition(String),
+    #[error("unknown stat `{0}`")]
+    UnknownStat(String),
+    #[error("unknown keyword `{0}`")]
+    UnknownKeyword(String),
+    #[error("`{0}` is not a valid number here")]
+    BadNumber(String),
+}
+
+/// Something wrong with the card pool file, see [`CardPool`](crate::CardPool).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum PoolError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("{location}: {problem}")]
+    Card {
+        location: Location,
+        problem: CardProblem,
+    },
+}
+
+/// Where a card is defined: the line of its row in `cards.csv`, its position in the list otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub row: usize,
+    pub field: Option<String>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(field) = &self.field {
+            write!(f, ", field `{}`", field)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CardProblem {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Ability(#[from] AbilityError),
+    #[error(
+        "`{0}` can't be used as a card id, only lowercase letters, digits and `_` are allowed"
+    )]
+    BadId(CardId),
+    #[error("`{id}` is already defined on row {row}")]
+    DuplicateId { id: CardId, row: usize },
+    #[error("`{name}` is already the name of the card on row {row}")]
+    DuplicateName { name: String, row: usize },
+    #[error("the name is empty")]
+    EmptyName,
+    #[error("{value} is outside of {min}..={max}")]
+    OutOfRange {
+        value: isize,
+        min: isize,
+        max: isize,
+    },
+    #[error("the portrait `{0}` does not exist")]
+    MissingPortrait(String),
+}
+
+/// A card id that is not in the [`CardRegistry`](crate::CardRegistry), e.g. a decklist or a
+/// server running a different card pool.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("unknown card `{0}`")]
+pub struct UnknownCard(pub CardId);
+
+/// Why a [`Decklist`](crate::Decklist) can't be played or saved.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DeckError {
+    #[error(transparent)]
+    UnknownCard(#[from] UnknownCard),
+    #[error("`{0}` can't be used as a deck name, use letters, digits, spaces, `-` and `_`")]
+    BadName(String),
+    #[error("a deck holds {expected} cards, this one has {actual}")]
+    WrongSize { expected: usize, actual: usize },
+    #[error("at most {max} copies of `{card}` are allowed")]
+    TooManyCopies { card: CardId, max: usize },
+    #[error("not a decklist: {0}")]
+    Malformed(String),
+}
+
+/// Something wrong with `balls.ron`, see [`BallKinds`](crate::BallKinds).
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BallKindError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error(
+        "`{0}` can't be used as a ball kind id, only lowercase letters, digits and `_` are allowed"
+    )]
+    BadId(BallKindId),
+    #[error("`{0}` is defined twice")]
+    Duplicate(BallKindId),
+    #[error("the {field} of `{id}` is {value}, outside of {min}..={max}")]
+    OutOfRange {
+        id: BallKindId,
+        field: &'static str,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+}
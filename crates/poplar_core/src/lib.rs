@@ -0,0 +1,48 @@
+//! Headless rules of CardPong.
+//!
+//! Everything in here is plain Rust: no window, no camera, no physics engine.
+//! The game drives a [`MatchState`] through [`MatchState::apply`] and reacts to
+//! the returned [`MatchEvent`]s, and `renet_server` runs the very same code.
+
+pub mod ability;
+pub mod action;
+pub mod ball;
+pub mod ball_kind;
+pub mod card;
+pub mod checksum;
+pub mod deck;
+pub mod decklist;
+pub mod error;
+pub mod paddle;
+pub mod pile;
+pub mod player;
+pub mod pool;
+pub mod protocol;
+pub mod registry;
+pub mod rng;
+pub mod state;
+#[cfg(test)]
+mod testing;
+
+pub use ability::{Abilities, Ability, Condition, Effect, Stat, Trigger};
+pub use action::{Action, EndReason, MatchEvent};
+pub use ball::{BallId, BallState, ServeRule, Table};
+pub use ball_kind::{BallKind, BallKindId, BallKinds};
+pub use card::{
+    Buff, CardId, CardInfo, CardInstance, CardInstanceId, CardStats, CardType, ISizeWithMax,
+    Keyword, Keywords,
+};
+pub use checksum::Checksum;
+pub use deck::Deck;
+pub use decklist::Decklist;
+pub use error::{
+    AbilityError, BallKindError, CardProblem, DeckError, Location, PoolError, RuleError,
+    UnknownCard,
+};
+pub use paddle::PaddleState;
+pub use pile::Pile;
+pub use player::{PlayerId, PlayerState, Seat, Zone};
+pub use pool::{CardPool, PoolFormat};
+pub use registry::CardRegistry;
+pub use rng::{random_seed, MatchRng};
+pub use state::{MatchResult, MatchState};
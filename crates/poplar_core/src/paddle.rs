@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ball::{BallState, Table},
+    player::Seat,
+};
+
+/// The bat a player defends their edge of the table with, it slides along that edge.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct PaddleState {
+    pub seat: Seat,
+    /// its center, across the table
+    pub x: f32,
+    /// how the player steers it: -1 to the left, 1 to the right, 0 holds it still
+    pub direction: i8,
+    /// percent added to its width by cards
+    pub width_bonus: isize,
+    /// percent added to its speed by cards
+    pub speed_bonus: isize,
+}
+
+impl PaddleState {
+    pub const WIDTH: f32 = 0.6;
+    pub const THICKNESS: f32 = 0.08;
+    /// distance covered in a second
+    pub const SPEED: f32 = 2.5;
+    /// between the paddle and the edge it defends
+    pub const INSET: f32 = 0.25;
+
+    /// A paddle in the middle of the edge of `seat`.
+    pub fn new(seat: Seat) -> Self {
+        Self {
+            seat,
+            x: 0.0,
+            direction: 0,
+            width_bonus: 0,
+            speed_bonus: 0,
+        }
+    }
+
+    /// Never below a fifth of the normal width, nor wider than the table.
+    pub fn width(&self, table: &Table) -> f32 {
+        let width = Self::WIDTH * (100 + self.width_bonus).max(20) as f32 / 100.0;
+        width.min(table.half_extents.x * 2.0)
+    }
+
+    /// Never below a tenth of the normal speed.
+    pub fn speed(&self) -> f32 {
+        Self::SPEED * (100 + self.speed_bonus).max(10) as f32 / 100.0
+    }
+
+    /// Where it slides, `One` defends the bottom edge.
+    pub fn y(&self, table: &Table) -> f32 {
+        let y = table.half_extents.y - Self::INSET;
+        match self.seat {
+            Seat::One => -y,
+            Seat::Two => y,
+        }
+    }
+
+    /// Slides the paddle the way it is steered, it stops at the side edges.
+    pub fn step(&mut self, dt: f32, table: &Table) {
+        let limit = (table.half_extents.x - self.width(table) / 2.0).max(0.0);
+        self.x = (self.x + self.direction as f32 * self.speed() * dt).clamp(-limit, limit);
+    }
+
+    /// Sends `ball` back at the opponent if it runs into the paddle on its way to the edge.
+    /// Returns whether it did.
+    pub fn bounce(&self, ball: &mut BallState, table: &Table) -> bool {
+        let incoming = match self.seat {
+            Seat::One => ball.velocity.y < 0.0,
+            Seat::Two => ball.velocity.y > 0.0,
+        };
+        let y = self.y(table);
+        let touches = (ball.position.x - self.x).abs() <= self.width(table) / 2.0 + ball.radius
+            && (ball.position.y - y).abs() <= Self::THICKNESS / 2.0 + ball.radius;
+        if ball.is_waiting() || !incoming || !touches {
+            return false;
+        }
+        ball.velocity.y = -ball.velocity.y;
+        true
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::card::{CardInstance, CardInstanceId};
+
+/// Cards that left play, the graveyard or the exile of a player.
+/// Cards are face up and keep the order they arrived in, the top of the pile is the end of the vec.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Pile {
+    cards: Vec<CardInstance>,
+}
+
+impl Pile {
+    pub fn push(&mut self, card: CardInstance) {
+        self.cards.push(card);
+    }
+
+    /// The card that arrived last.
+    pub fn top(&self) -> Option<&CardInstance> {
+        self.cards.last()
+    }
+
+    /// The cards `matches` accepts, from the top down.
+    pub fn search<'a>(
+        &'a self,
+        mut matches: impl FnMut(&CardInstance) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a CardInstance> {
+        self.cards.iter().rev().filter(move |card| matches(card))
+    }
+
+    /// Takes a given card out of the pile, wherever it is.
+    pub fn remove(&mut self, id: CardInstanceId) -> Option<CardInstance> {
+        let index = self.cards.iter().position(|card| card.id == id)?;
+        Some(self.cards.remove(index))
+    }
+
+    pub fn contains(&self, id: CardInstanceId) -> bool {
+        self.cards.iter().any(|card| card.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// From the bottom up.
+    pub fn iter(&self) -> impl Iterator<Item = &CardInstance> {
+        self.cards.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut CardInstance> {
+        self.cards.iter_mut()
+    }
+}
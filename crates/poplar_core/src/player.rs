@@ -0,0 +1,227 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{CardInstance, CardInstanceId},
+    deck::Deck,
+    pile::Pile,
+};
+
+/// One of the two sides of the table. `One` sits at the bottom (negative y).
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::reflect::Reflect))]
+pub enum Seat {
+    #[default]
+    One,
+    Two,
+}
+
+impl Seat {
+    pub const ALL: [Seat; 2] = [Seat::One, Seat::Two];
+
+    pub fn index(self) -> usize {
+        match self {
+            Seat::One => 0,
+            Seat::Two => 1,
+        }
+    }
+
+    pub fn opponent(self) -> Seat {
+        match self {
+            Seat::One => Seat::Two,
+            Seat::Two => Seat::One,
+        }
+    }
+}
+
+/// Where a card of a player is. Equipment counts as on the battlefield, with its creature.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Zone {
+    Deck,
+    Hand,
+    Battlefield,
+    Field,
+    Graveyard,
+    /// removed from the game, nothing brings a card back from here but an effect
+    Exile,
+}
+
+impl Zone {
+    pub fn is_in_play(self) -> bool {
+        matches!(self, Zone::Battlefield | Zone::Field)
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Deck => write!(f, "deck"),
+            Zone::Hand => write!(f, "hand"),
+            Zone::Battlefield => write!(f, "battlefield"),
+            Zone::Field => write!(f, "field"),
+            Zone::Graveyard => write!(f, "graveyard"),
+            Zone::Exile => write!(f, "exile"),
+        }
+    }
+}
+
+/// Who is playing a seat.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PlayerId {
+    /// both seats of a local match are played on the same machine
+    Local(Seat),
+    /// the raw renet `ClientId` of a networked player
+    Client(u64),
+}
+
+impl Default for PlayerId {
+    fn default() -> Self {
+        PlayerId::Local(Seat::default())
+    }
+}
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerId::Local(seat) => write!(f, "local {:?}", seat),
+            PlayerId::Client(id) => write!(f, "client {}", id),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerState {
+    pub id: PlayerId,
+    pub seat: Seat,
+    pub health: i32,
+    /// spent to play cards, see [`MatchState::regenerate`](crate::MatchState::regenerate)
+    pub energy: u32,
+    /// how far the next point of energy is, from 0 to 1
+    pub energy_progress: f32,
+    pub deck: Deck,
+    pub hand: Vec<CardInstance>,
+    pub battlefield: Vec<Option<CardInstance>>,
+    pub field: Option<CardInstance>,
+    pub graveyard: Pile,
+    pub exile: Pile,
+}
+
+impl PlayerState {
+    pub const DEFAULT_HEALTH: i32 = 20;
+    pub const HAND_SIZE: usize = 5;
+    pub const BATTLEFIELD_SIZE: usize = 5;
+    pub const STARTING_ENERGY: u32 = 3;
+    pub const MAX_ENERGY: u32 = 10;
+    /// points of energy gained per second
+    pub const ENERGY_RATE: f32 = 0.5;
+
+    pub fn new(seat: Seat, deck: Deck) -> Self {
+        Self {
+            id: PlayerId::Local(seat),
+            seat,
+            health: Self::DEFAULT_HEALTH,
+            energy: Self::STARTING_ENERGY,
+            energy_progress: 0.0,
+            deck,
+            hand: Vec::new(),
+            battlefield: vec![None; Self::BATTLEFIELD_SIZE],
+            field: None,
+            graveyard: Pile::default(),
+            exile: Pile::default(),
+        }
+    }
+
+    /// Nothing left to draw or play and nothing on the battlefield.
+    pub fn is_out_of_cards(&self) -> bool {
+        self.deck.is_empty() && self.hand.is_empty() && self.battlefield.iter().all(Option::is_none)
+    }
+
+    /// Sends a card that left play to the graveyard, along with its equipment.
+    /// Returns the equipment that went with it.
+    pub fn bury(&mut self, mut card: CardInstance) -> Vec<CardInstanceId> {
+        let equipment = self.bury_equipment(&mut card);
+        self.graveyard.push(card);
+        equipment
+    }
+
+    /// Removes a card from the game, its equipment goes to the graveyard and is returned.
+    pub fn exile(&mut self, mut card: CardInstance) -> Vec<CardInstanceId> {
+        let equipment = self.bury_equipment(&mut card);
+        self.exile.push(card);
+        equipment
+    }
+
+    fn bury_equipment(&mut self, card: &mut CardInstance) -> Vec<CardInstanceId> {
+        card.reset()
+            .into_iter()
+            .map(|mut equipment| {
+                equipment.reset();
+                let id = equipment.id;
+                self.graveyard.push(equipment);
+                id
+            })
+            .collect()
+    }
+
+    pub fn zone_of(&self, card: CardInstanceId) -> Option<Zone> {
+        let is = |instance: &CardInstance| instance.id == card;
+        if self.hand.iter().any(is) {
+            Some(Zone::Hand)
+        } else if self
+            .battlefield
+            .iter()
+            .flatten()
+            .any(|creature| is(creature) || creature.attached.iter().any(is))
+        {
+            Some(Zone::Battlefield)
+        } else if self.field.as_ref().is_some_and(is) {
+            Some(Zone::Field)
+        } else if self.deck.iter().any(is) {
+            Some(Zone::Deck)
+        } else if self.graveyard.contains(card) {
+            Some(Zone::Graveyard)
+        } else if self.exile.contains(card) {
+            Some(Zone::Exile)
+        } else {
+            None
+        }
+    }
+
+    /// Takes a card out of whatever zone it is in, equipment is taken off its creature.
+    pub fn take(&mut self, card: CardInstanceId) -> Option<(Zone, CardInstance)> {
+        let zone = self.zone_of(card)?;
+        let instance = match zone {
+            Zone::Deck => self.deck.remove(card),
+            Zone::Hand => self.hand_index(card).map(|index| self.hand.remove(index)),
+            Zone::Battlefield => match self.battlefield_index(card) {
+                Some(slot) => self.battlefield[slot].take(),
+                None => self.battlefield.iter_mut().flatten().find_map(|creature| {
+                    let index = creature
+                        .attached
+                        .iter()
+                        .position(|equipment| equipment.id == card)?;
+                    Some(creature.attached.remove(index))
+                }),
+            },
+            Zone::Field => self.field.take(),
+            Zone::Graveyard => self.graveyard.remove(card),
+            Zone::Exile => self.exile.remove(card),
+        }?;
+        Some((zone, instance))
+    }
+
+    pub fn hand_index(&self, card: CardInstanceId) -> Option<usize> {
+        self.hand.iter().position(|c| c.id == card)
+    }
+
+    pub fn battlefield_index(&self, card: CardInstanceId) -> Option<usize> {
+        self.battlefield
+            .iter()
+            .position(|c| c.as_ref().is_some_and(|c| c.id == card))
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0
+    }
+}
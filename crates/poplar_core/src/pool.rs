@@ -0,0 +1,571 @@
+//! The card pool: every card definition of the game, checked before anyone gets to play.
+//!
+//! `cards.csv` holds one card per row, with the keywords and abilities in their
+//! text form (see [`ability`](crate::ability)). `cards.ron` and `cards.json` hold a
+//! list of cards with the keywords and abilities written out as data instead:
+//!
+//! ```text
+//! [
+//!     (
+//!         id: "zombie",
+//!         name: "zombie",
+//!         name_zh: "僵尸",
+//!         desc: "zombie",
+//!         card_type: creature,
+//!         cost: 2,
+//!         cooldown: 1,
+//!         attack: 1,
+//!         health: 2,
+//!         keywords: [Swift],
+//!         abilities: [
+//!             (trigger: Tick(every: 10.0), effect: Draw(1), condition: Some(HandBelow(3))),
+//!             (trigger: Death, effect: SpawnBall("standard")),
+//!         ],
+//!         art: "zombie.png",
+//!     ),
+//! ]
+//! ```
+
+use std::{collections::HashMap, ops::RangeInclusive};
+
+use csv::StringRecord;
+use serde::{
+    de::{
+        value::{self, StrDeserializer},
+        DeserializeOwned, IntoDeserializer,
+    },
+    Deserialize,
+};
+
+use crate::{
+    ability::{Abilities, Ability, Trigger},
+    card::{CardId, CardInfo, CardStats, CardType, Keyword, Keywords},
+    error::{AbilityError, CardProblem, Location, PoolError},
+};
+
+const COST: RangeInclusive<isize> = 0..=10;
+const COOLDOWN: RangeInclusive<isize> = 0..=30;
+const ATTACK: RangeInclusive<isize> = 0..=20;
+const HEALTH: RangeInclusive<isize> = 0..=30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolFormat {
+    Csv,
+    Ron,
+    Json,
+}
+
+impl PoolFormat {
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "csv" => Some(PoolFormat::Csv),
+            "ron" => Some(PoolFormat::Ron),
+            "json" => Some(PoolFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Every card of the game along with the row it is defined on.
+/// A pool with [`errors`](CardPool::errors) must not be played with.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(
+    feature = "bevy",
+    derive(
+        bevy::asset::Asset,
+        bevy::reflect::TypePath,
+        bevy::ecs::system::Resource
+    )
+)]
+pub struct CardPool {
+    cards: Vec<(usize, CardInfo)>,
+    errors: Vec<PoolError>,
+}
+
+impl CardPool {
+    /// Reads and checks a pool, collecting every problem instead of stopping at the first one.
+    pub fn parse(format: PoolFormat, bytes: &[u8]) -> Self {
+        let mut pool = match format {
+            PoolFormat::Csv => Self::parse_csv(bytes),
+            PoolFormat::Ron => {
+                Self::from_entries(ron::de::from_bytes(bytes).map_err(|err| PoolError::Parse {
+                    line: err.position.line,
+                    message: err.code.to_string(),
+                }))
+            }
+            PoolFormat::Json => Self::from_entries(serde_json::from_slice(bytes).map_err(|err| {
+                // the message already ends with the position
+                let position = format!(" at line {} column {}", err.line(), err.column());
+                let message = err.to_string();
+                PoolError::Parse {
+                    line: err.line(),
+                    message: message.trim_end_matches(&position).to_string(),
+                }
+            })),
+        };
+        pool.validate();
+        pool
+    }
+
+    pub fn cards(&self) -> impl Iterator<Item = &CardInfo> {
+        self.cards.iter().map(|(_, card)| card)
+    }
+
+    /// The cards with the row they are defined on, to report problems found outside the rules.
+    pub fn rows(&self) -> impl Iterator<Item = (usize, &CardInfo)> {
+        self.cards.iter().map(|(row, card)| (*row, card))
+    }
+
+    pub fn errors(&self) -> &[PoolError] {
+        &self.errors
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Records a problem with `field` of the card defined on `row`.
+    pub fn report(&mut self, row: usize, field: &str, problem: CardProblem) {
+        self.errors.push(PoolError::Card {
+            location: Location {
+                row,
+                field: Some(field.to_string()),
+            },
+            problem,
+        });
+    }
+
+    fn parse_csv(bytes: &[u8]) -> Self {
+        let mut pool = Self::default();
+        let mut reader = csv::Reader::from_reader(bytes);
+        let headers = match reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(err) => {
+                pool.errors.push(csv_error(&err));
+                return pool;
+            }
+        };
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(err) => {
+                    pool.errors.push(csv_error(&err));
+                    continue;
+                }
+            };
+            let row = record
+                .position()
+                .map_or(0, |position| position.line() as usize);
+            match record.deserialize::<CardInfo>(Some(&headers)) {
+                Ok(card) => pool.cards.push((row, card)),
+                Err(err) => {
+                    let csv::ErrorKind::Deserialize { err, .. } = err.kind() else {
+                        pool.errors.push(csv_error(&err));
+                        continue;
+                    };
+                    let field = match err.field() {
+                        Some(index) => headers.get(index as usize),
+                        None => failing_column(&headers, &record),
+                    };
+                    pool.errors.push(PoolError::Card {
+                        location: Location {
+                            row,
+                            field: field.map(str::to_string),
+                        },
+                        problem: CardProblem::Invalid(err.kind().to_string()),
+                    });
+                }
+            }
+        }
+        pool
+    }
+
+    fn from_entries(entries: Result<Vec<CardEntry>, PoolError>) -> Self {
+        match entries {
+            Ok(entries) => Self {
+                cards: (1..).zip(entries.into_iter().map(CardInfo::from)).collect(),
+                errors: Vec::new(),
+            },
+            Err(err) => Self {
+                cards: Vec::new(),
+                errors: vec![err],
+            },
+        }
+    }
+
+    fn validate(&mut self) {
+        let mut ids = HashMap::new();
+        let mut names = HashMap::new();
+        let mut problems = Vec::new();
+        for (row, card) in self.cards.iter() {
+            let row = *row;
+            if !card.id.is_valid() {
+                problems.push((row, "id", CardProblem::BadId(card.id.clone())));
+            } else if let Some(&first) = ids.get(&card.id) {
+                problems.push((
+                    row,
+                    "id",
+                    CardProblem::DuplicateId {
+                        id: card.id.clone(),
+                        row: first,
+                    },
+                ));
+            } else {
+                ids.insert(&card.id, row);
+            }
+            // two cards under one name can't be told apart in a decklist or the deck builder
+            if card.name.trim().is_empty() {
+                problems.push((row, "name", CardProblem::EmptyName));
+            } else if let Some(&first) = names.get(card.name.as_str()) {
+                problems.push((
+                    row,
+                    "name",
+                    CardProblem::DuplicateName {
+                        name: card.name.clone(),
+                        row: first,
+                    },
+                ));
+            } else {
+                names.insert(card.name.as_str(), row);
+            }
+
+            // a creature without health would die as soon as it is played
+            let health = match card.card_type {
+                CardType::Creature => 1..=*HEALTH.end(),
+                _ => HEALTH,
+            };
+            let CardStats {
+                cost,
+                cooldown,
+                attack,
+                health: value,
+            } = card.stats;
+            for (field, value, range) in [
+                ("cost", cost, COST),
+                ("cooldown", cooldown, COOLDOWN),
+                ("attack", attack, ATTACK),
+                ("health", value, health),
+            ] {
+                if !range.contains(&value) {
+                    problems.push((
+                        row,
+                        field,
+                        CardProblem::OutOfRange {
+                            value,
+                            min: *range.start(),
+                            max: *range.end(),
+                        },
+                    ));
+                }
+            }
+
+            // the text form is refused these while parsing, RON and JSON come here unchecked
+            for ability in card.abilities.iter() {
+                if let Trigger::Tick { every } = ability.trigger {
+                    if !every.is_finite() || every <= 0.0 {
+                        let problem = AbilityError::BadNumber(every.to_string()).into();
+                        problems.push((row, "abilities", problem));
+                    }
+                }
+            }
+        }
+        for (row, field, problem) in problems {
+            self.report(row, field, problem);
+        }
+    }
+}
+
+fn csv_error(err: &csv::Error) -> PoolError {
+    let line = err
+        .position()
+        .map_or(0, |position| position.line() as usize);
+    let message = match err.kind() {
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!("found {} fields instead of {}", len, expected_len),
+        _ => err.to_string(),
+    };
+    PoolError::Parse { line, message }
+}
+
+// csv only knows the column of the errors it raises itself, not of those
+// raised by our types, so look for the column that doesn't parse on its own
+fn failing_column<'a>(headers: &'a StringRecord, record: &StringRecord) -> Option<&'a str> {
+    fn parses<T: DeserializeOwned>(value: &str) -> bool {
+        let deserializer: StrDeserializer<'_, value::Error> = value.into_deserializer();
+        T::deserialize(deserializer).is_ok()
+    }
+    headers
+        .iter()
+        .zip(record.iter())
+        .find(|(header, value)| match *header {
+            "card_type" => !parses::<CardType>(value),
+            "keywords" => !parses::<Keywords>(value),
+            "abilities" => !parses::<Abilities>(value),
+            _ => false,
+        })
+        .map(|(header, _)| header)
+}
+
+/// One card of `cards.ron` or `cards.json`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CardEntry {
+    id: CardId,
+    name: String,
+    name_zh: String,
+    #[serde(default)]
+    desc: String,
+    card_type: CardType,
+    cost: isize,
+    cooldown: isize,
+    #[serde(default)]
+    attack: isize,
+    #[serde(default)]
+    health: isize,
+    #[serde(default)]
+    keywords: Vec<Keyword>,
+    #[serde(default)]
+    abilities: Vec<Ability>,
+    art: String,
+}
+
+impl From<CardEntry> for CardInfo {
+    fn from(entry: CardEntry) -> Self {
+        Self {
+            id: entry.id,
+            name: entry.name,
+            name_zh: entry.name_zh,
+            desc: entry.desc,
+            card_type: entry.card_type,
+            stats: CardStats {
+                cost: entry.cost,
+                cooldown: entry.cooldown,
+                attack: entry.attack,
+                health: entry.health,
+            },
+            keywords: Keywords(entry.keywords),
+            abilities: Abilities(entry.abilities),
+            art: entry.art,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str =
+        "id,name,name_zh,card_type,cost,cooldown,attack,health,keywords,desc,abilities,art\n";
+
+    fn csv(rows: &str) -> CardPool {
+        CardPool::parse(PoolFormat::Csv, format!("{}{}", HEADER, rows).as_bytes())
+    }
+
+    fn ron_card(fields: &str) -> String {
+        format!(
+            "[(id: \"card\", name: \"card\", name_zh: \"卡\", card_type: creature, cost: 1, \
+             cooldown: 1, attack: 1, health: 1, art: \"card.png\", {})]",
+            fields
+        )
+    }
+
+    fn problems(pool: &CardPool) -> Vec<(usize, Option<&str>, &CardProblem)> {
+        pool.errors()
+            .iter()
+            .map(|error| match error {
+                PoolError::Card { location, problem } => {
+                    (location.row, location.field.as_deref(), problem)
+                }
+                PoolError::Parse { .. } => panic!("{}", error),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tick_interval_must_be_finite() {
+        for every in ["0.0", "-1.0", "inf", "NaN"] {
+            let abilities = format!(
+                "abilities: [(trigger: Tick(every: {}), effect: Draw(1))]",
+                every
+            );
+            let pool = CardPool::parse(PoolFormat::Ron, ron_card(&abilities).as_bytes());
+            let problems = problems(&pool);
+            assert!(
+                matches!(
+                    problems[..],
+                    [(
+                        1,
+                        Some("abilities"),
+                        CardProblem::Ability(AbilityError::BadNumber(_))
+                    )]
+                ),
+                "{}: {:?}",
+                every,
+                problems
+            );
+        }
+    }
+
+    #[test]
+    fn bad_id() {
+        let pool = csv("Big Rat,rat,鼠,creature,1,1,1,1,,rat,,rat.png\n");
+        assert_eq!(
+            problems(&pool),
+            [(2, Some("id"), &CardProblem::BadId(CardId::from("Big Rat")))]
+        );
+    }
+
+    #[test]
+    fn duplicate_id() {
+        let pool = csv("rat,rat,鼠,creature,1,1,1,1,,rat,,rat.png\n\
+             cat,cat,猫,creature,1,1,1,1,,cat,,cat.png\n\
+             rat,big rat,大鼠,creature,2,1,2,2,,rat,,rat.png\n");
+        let duplicate = CardProblem::DuplicateId {
+            id: CardId::from("rat"),
+            row: 2,
+        };
+        assert_eq!(problems(&pool), [(4, Some("id"), &duplicate)]);
+        // the first one still counts
+        assert_eq!(pool.cards().count(), 3);
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let pool = csv("rat,rat,鼠,creature,1,1,1,1,,rat,,rat.png\n\
+             big_rat,rat,大鼠,creature,2,1,2,2,,rat,,rat.png\n\
+             nameless,  ,无名,creature,1,1,1,1,,nameless,,nameless.png\n");
+        let duplicate = CardProblem::DuplicateName {
+            name: "rat".to_string(),
+            row: 2,
+        };
+        assert_eq!(
+            problems(&pool),
+            [
+                (3, Some("name"), &duplicate),
+                (4, Some("name"), &CardProblem::EmptyName)
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_stats() {
+        let pool = csv("rat,rat,鼠,creature,11,31,21,31,,rat,,rat.png\n\
+             hex,hex,咒,spell,-1,-1,-1,-1,,hex,,hex.png\n");
+        let out_of = |value, range: RangeInclusive<isize>| CardProblem::OutOfRange {
+            value,
+            min: *range.start(),
+            max: *range.end(),
+        };
+        assert_eq!(
+            problems(&pool),
+            [
+                (2, Some("cost"), &out_of(11, COST)),
+                (2, Some("cooldown"), &out_of(31, COOLDOWN)),
+                (2, Some("attack"), &out_of(21, ATTACK)),
+                (2, Some("health"), &out_of(31, 1..=30)),
+                (3, Some("cost"), &out_of(-1, COST)),
+                (3, Some("cooldown"), &out_of(-1, COOLDOWN)),
+                (3, Some("attack"), &out_of(-1, ATTACK)),
+                (3, Some("health"), &out_of(-1, HEALTH)),
+            ]
+        );
+    }
+
+    #[test]
+    fn creature_without_health() {
+        let pool = csv("rat,rat,鼠,creature,1,1,1,0,,rat,,rat.png\n\
+             hex,hex,咒,spell,1,1,0,0,,hex,,hex.png\n");
+        let no_health = CardProblem::OutOfRange {
+            value: 0,
+            min: 1,
+            max: *HEALTH.end(),
+        };
+        assert_eq!(problems(&pool), [(2, Some("health"), &no_health)]);
+    }
+
+    #[test]
+    fn csv_errors_point_at_the_field() {
+        let pool = csv("rat,rat,鼠,creature,1,1,1,1,,rat,,rat.png\n\
+             cat,cat,猫,dragon,1,1,1,1,,cat,,cat.png\n\
+             dog,dog,狗,creature,many,1,1,1,,dog,,dog.png\n\
+             owl,owl,鸮,creature,1,1,1,1,,owl,jump: draw 1,owl.png\n");
+        let fields: Vec<_> = problems(&pool)
+            .into_iter()
+            .map(|(row, field, problem)| {
+                assert!(matches!(problem, CardProblem::Invalid(_)), "{:?}", problem);
+                (row, field)
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (3, Some("card_type")),
+                (4, Some("cost")),
+                (5, Some("abilities"))
+            ]
+        );
+        assert_eq!(pool.cards().count(), 1);
+    }
+
+    #[test]
+    fn csv_row_of_the_wrong_length() {
+        let pool = csv("rat,rat,鼠,creature,1,1,1,1,,rat,,rat.png\ncat,cat\n");
+        assert!(
+            matches!(pool.errors(), [PoolError::Parse { line: 3, .. }]),
+            "{:?}",
+            pool.errors()
+        );
+    }
+
+    #[test]
+    fn ron_and_json_agree() {
+        let ron = r#"[
+            (
+                id: "zombie",
+                name: "zombie",
+                name_zh: "僵尸",
+                desc: "zombie",
+                card_type: creature,
+                cost: 2,
+                cooldown: 1,
+                attack: 1,
+                health: 2,
+                keywords: [Swift],
+                abilities: [
+                    (trigger: Tick(every: 10.0), effect: Draw(1), condition: Some(HandBelow(3))),
+                    (trigger: Death, effect: SpawnBall("standard")),
+                ],
+                art: "zombie.png",
+            ),
+        ]"#;
+        let json = r#"[
+            {
+                "id": "zombie",
+                "name": "zombie",
+                "name_zh": "僵尸",
+                "desc": "zombie",
+                "card_type": "creature",
+                "cost": 2,
+                "cooldown": 1,
+                "attack": 1,
+                "health": 2,
+                "keywords": ["Swift"],
+                "abilities": [
+                    {"trigger": {"Tick": {"every": 10.0}}, "effect": {"Draw": 1}, "condition": {"HandBelow": 3}},
+                    {"trigger": "Death", "effect": {"SpawnBall": "standard"}}
+                ],
+                "art": "zombie.png"
+            }
+        ]"#;
+        let ron = CardPool::parse(PoolFormat::Ron, ron.as_bytes());
+        let json = CardPool::parse(PoolFormat::Json, json.as_bytes());
+        assert!(ron.is_valid(), "{:?}", ron.errors());
+        assert!(json.is_valid(), "{:?}", json.errors());
+        assert_eq!(ron.cards().count(), 1);
+        assert_eq!(
+            format!("{:?}", ron.rows().collect::<Vec<_>>()),
+            format!("{:?}", json.rows().collect::<Vec<_>>())
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Everything the game and `renet_server` say to each other.
+//!
+//! Both sides must be built from the same version of this module: the
+//! [`PROTOCOL_ID`] changes with [`PROTOCOL_VERSION`] so netcode refuses
+//! mismatched builds, and the [`ClientMessage::Hello`] handshake reports it.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    action::{Action, MatchEvent},
+    ball::BallState,
+    decklist::Decklist,
+    paddle::PaddleState,
+    player::Seat,
+    state::MatchState,
+};
+
+/// Bump on every change to the messages below.
+pub const PROTOCOL_VERSION: u32 = 20;
+const GAME_ID: u64 = 7;
+pub const PROTOCOL_ID: u64 = (GAME_ID << 32) | PROTOCOL_VERSION as u64;
+
+pub enum ClientChannel {
+    Input,
+    Command,
+}
+
+pub enum ServerChannel {
+    ServerMessages,
+    NetworkedEntities,
+}
+
+impl From<ClientChannel> for u8 {
+    fn from(channel_id: ClientChannel) -> Self {
+        match channel_id {
+            ClientChannel::Command => 0,
+            ClientChannel::Input => 1,
+        }
+    }
+}
+
+impl From<ServerChannel> for u8 {
+    fn from(channel_id: ServerChannel) -> Self {
+        match channel_id {
+            ServerChannel::NetworkedEntities => 0,
+            ServerChannel::ServerMessages => 1,
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+mod channels {
+    use std::time::Duration;
+
+    use renet::{ChannelConfig, ConnectionConfig, SendType};
+
+    use super::{ClientChannel, ServerChannel};
+
+    impl ClientChannel {
+        pub fn channels_config() -> Vec<ChannelConfig> {
+            vec![
+                ChannelConfig {
+                    channel_id: Self::Input.into(),
+                    max_memory_usage_bytes: 5 * 1024 * 1024,
+                    send_type: SendType::Unreliable,
+                },
+                ChannelConfig {
+                    channel_id: Self::Command.into(),
+                    max_memory_usage_bytes: 5 * 1024 * 1024,
+                    send_type: SendType::ReliableOrdered {
+                        resend_time: Duration::ZERO,
+                    },
+                },
+            ]
+        }
+    }
+
+    impl ServerChannel {
+        pub fn channels_config() -> Vec<ChannelConfig> {
+            vec![
+                ChannelConfig {
+                    channel_id: Self::NetworkedEntities.into(),
+                    max_memory_usage_bytes: 10 * 1024 * 1024,
+                    send_type: SendType::Unreliable,
+                },
+                ChannelConfig {
+                    channel_id: Self::ServerMessages.into(),
+                    max_memory_usage_bytes: 10 * 1024 * 1024,
+                    send_type: SendType::ReliableOrdered {
+                        resend_time: Duration::from_millis(200),
+                    },
+                },
+            ]
+        }
+    }
+
+    pub fn connection_config() -> ConnectionConfig {
+        ConnectionConfig {
+            available_bytes_per_tick: 1024 * 1024,
+            client_channels_config: ClientChannel::channels_config(),
+            server_channels_config: ServerChannel::channels_config(),
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+pub use channels::connection_config;
+
+/// Sent by clients on [`ClientChannel::Command`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    /// first message after connecting, nothing else is accepted before it
+    Hello {
+        version: u32,
+    },
+    /// the deck to play the next match with, before getting ready. Without one the player gets the starter deck
+    Deck(Decklist),
+    /// the match starts once every seat is taken and ready
+    Ready(bool),
+    Action(Action),
+}
+
+/// How the keys steering a paddle changed at `tick`. Clients send the last [`PlayerInput::RESENT`]
+/// of them, oldest first, on [`ClientChannel::Input`] every tick: the channel is unreliable, the next
+/// message makes up for a lost one. The server holds on to each until its match reaches `tick`,
+/// the client predicted its paddle from then on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlayerInput {
+    pub tick: u64,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl PlayerInput {
+    /// changes repeated in every message, a burst of lost packets longer than this loses one
+    pub const RESENT: usize = 4;
+
+    /// The way the paddle goes, see [`PaddleState::direction`].
+    pub fn direction(&self) -> i8 {
+        self.right as i8 - self.left as i8
+    }
+}
+
+/// One seated client as shown in the room menu.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LobbyEntry {
+    pub id: u64,
+    pub seat: Seat,
+    pub ready: bool,
+}
+
+/// Sent by the server on [`ServerChannel::ServerMessages`]. Client ids are the raw renet `ClientId`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ServerMessage {
+    Welcome {
+        id: u64,
+        seat: Seat,
+    },
+    Refused {
+        reason: String,
+    },
+    PlayerConnected {
+        id: u64,
+    },
+    PlayerDisconnected {
+        id: u64,
+    },
+    Lobby(Vec<LobbyEntry>),
+    /// the deck sent can't be played with the cards of the server
+    DeckRejected {
+        reason: String,
+    },
+    /// `state` is the [view](MatchState::view_for) of `seat`
+    MatchStarted {
+        seat: Seat,
+        state: Box<MatchState>,
+    },
+    /// the events as `seat` may see them, see [`MatchState::events_for`]
+    Match(Vec<MatchEvent>),
+    ActionRejected {
+        action: Action,
+        reason: String,
+    },
+}
+
+/// What changes every tick, sent on [`ServerChannel::NetworkedEntities`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub balls: Vec<BallState>,
+    /// where the paddles are and how they are steered, clients predict from there
+    pub paddles: [PaddleState; 2],
+    pub health: [i32; 2],
+    /// [`MatchState::checksum`] after `tick`
+    pub checksum: u64,
+}
+
+impl Snapshot {
+    pub fn of(match_state: &MatchState) -> Self {
+        Self {
+            tick: match_state.tick,
+            balls: match_state.balls.clone(),
+            paddles: Seat::ALL.map(|seat| *match_state.paddle(seat)),
+            health: Seat::ALL.map(|seat| match_state.player(seat).health),
+            checksum: match_state.checksum(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ProtocolError {
+    #[error("malformed message: {0}")]
+    Malformed(#[from] bincode::Error),
+}
+
+pub fn encode<T: Serialize>(message: &T) -> Vec<u8> {
+    bincode::serialize(message).expect("protocol messages always serialize")
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, ProtocolError> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card::CardInstanceId, testing};
+
+    const CARDS: &str = "\
+soldier,soldier,士兵,creature,1,1,1,3,armored,soldier,play: damage 1,soldier.png
+";
+
+    fn round_trip<T: Serialize + DeserializeOwned>(message: &T) -> T {
+        decode(&encode(message)).unwrap()
+    }
+
+    // the server messages carry a match, which can only be compared by what it prints
+    fn assert_round_trip(message: &ServerMessage) {
+        assert_eq!(
+            format!("{:?}", round_trip(message)),
+            format!("{:?}", message)
+        );
+    }
+
+    #[test]
+    fn client_messages() {
+        let mut decklist = Decklist::new("soldiers");
+        decklist.add(&"soldier".into()).unwrap();
+        for message in [
+            ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+            },
+            ClientMessage::Deck(decklist),
+            ClientMessage::Ready(true),
+            ClientMessage::Action(Action::DrawCard),
+            ClientMessage::Action(Action::PlayCard {
+                card: CardInstanceId(3),
+                slot: 2,
+            }),
+            ClientMessage::Action(Action::MoveCard {
+                card: CardInstanceId(3),
+                slot: 4,
+            }),
+            ClientMessage::Action(Action::Concede),
+        ] {
+            assert_eq!(round_trip(&message), message);
+        }
+    }
+
+    #[test]
+    fn player_input() {
+        let inputs = vec![
+            PlayerInput {
+                tick: 1234,
+                left: true,
+                right: false,
+            },
+            PlayerInput {
+                tick: 1240,
+                left: false,
+                right: false,
+            },
+        ];
+        assert_eq!(round_trip(&inputs), inputs);
+    }
+
+    #[test]
+    fn server_messages() {
+        let registry = testing::registry(CARDS);
+        let mut state = testing::match_state(&registry, &["soldier"; 10], 3);
+        let events = state.deal();
+        for message in [
+            ServerMessage::Welcome {
+                id: 42,
+                seat: Seat::Two,
+            },
+            ServerMessage::Refused {
+                reason: "the room is full".to_string(),
+            },
+            ServerMessage::PlayerConnected { id: 42 },
+            ServerMessage::PlayerDisconnected { id: 42 },
+            ServerMessage::Lobby(vec![LobbyEntry {
+                id: 42,
+                seat: Seat::One,
+                ready: true,
+            }]),
+            ServerMessage::DeckRejected {
+                reason: "unknown card".to_string(),
+            },
+            ServerMessage::Match(events),
+            ServerMessage::ActionRejected {
+                action: Action::DrawCard,
+                reason: "the hand is full".to_string(),
+            },
+        ] {
+            assert_round_trip(&message);
+        }
+    }
+
+    #[test]
+    fn match_started() {
+        let registry = testing::registry(CARDS);
+        let mut state = testing::match_state(&registry, &["soldier"; 10], 3);
+        state.deal();
+        let view = state.view_for(Seat::One);
+        let message = ServerMessage::MatchStarted {
+            seat: Seat::One,
+            state: Box::new(view.clone()),
+        };
+        let ServerMessage::MatchStarted {
+            seat,
+            state: mut received,
+        } = round_trip(&message)
+        else {
+            panic!("not a match start");
+        };
+        assert_eq!(seat, Seat::One);
+        // only the card ids made it, the rest comes from the registry
+        let card = received.player(Seat::One).hand[0].id;
+        assert!(received.card(card).unwrap().info.name.is_empty());
+        received.link(&registry).unwrap();
+        assert_eq!(format!("{:?}", received), format!("{:?}", view));
+        assert_eq!(received.checksum(), state.checksum());
+    }
+
+    #[test]
+    fn snapshot() {
+        let registry = testing::registry(CARDS);
+        let mut state = testing::match_state(&registry, &["soldier"; 10], 3);
+        state.deal();
+        state.steer(Seat::Two, -1);
+        for _ in 0..10 {
+            state.tick(MatchState::TIMESTEP);
+        }
+        let snapshot = Snapshot::of(&state);
+        assert_eq!(round_trip(&snapshot), snapshot);
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    card::{CardId, CardInfo},
+    error::UnknownCard,
+    pool::CardPool,
+};
+
+/// Every card definition of the game by id, in id order.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy::ecs::system::Resource))]
+pub struct CardRegistry {
+    cards: BTreeMap<CardId, CardInfo>,
+}
+
+impl CardRegistry {
+    pub fn get(&self, id: &CardId) -> Result<&CardInfo, UnknownCard> {
+        self.cards.get(id).ok_or_else(|| UnknownCard(id.clone()))
+    }
+
+    pub fn contains(&self, id: &CardId) -> bool {
+        self.cards.contains_key(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &CardId> {
+        self.cards.keys()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CardInfo> {
+        self.cards.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+/// Only build a registry from a [valid](CardPool::is_valid) pool, a duplicated id keeps its last definition.
+impl From<&CardPool> for CardRegistry {
+    fn from(pool: &CardPool) -> Self {
+        Self {
+            cards: pool
+                .cards()
+                .map(|card| (card.id.clone(), card.clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PoolFormat;
+
+    fn shipped() -> CardRegistry {
+        let pool = CardPool::parse(PoolFormat::Csv, include_bytes!("../../../assets/cards.csv"));
+        assert!(pool.is_valid(), "{:?}", pool.errors());
+        CardRegistry::from(&pool)
+    }
+
+    #[test]
+    fn get_by_id() {
+        let registry = shipped();
+        let soldier = registry.get(&CardId::from("human_soldier")).unwrap();
+        assert_eq!(soldier.id, CardId::from("human_soldier"));
+        assert_eq!(soldier.name, "human soldier");
+        // the name is only shown, it is no id
+        let by_name = CardId::from("human soldier");
+        assert!(!registry.contains(&by_name));
+        assert_eq!(registry.get(&by_name).unwrap_err(), UnknownCard(by_name));
+    }
+
+    #[test]
+    fn portrait_from_art() {
+        let registry = shipped();
+        let soldier = registry.get(&CardId::from("human_soldier")).unwrap();
+        assert_eq!(soldier.art, "human soldier.png");
+        // every portrait ships next to the pool
+        for card in registry.iter() {
+            let path = format!("{}/../../assets/{}", env!("CARGO_MANIFEST_DIR"), card.art);
+            assert!(std::path::Path::new(&path).is_file(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn in_id_order() {
+        let registry = shipped();
+        let ids: Vec<_> = registry.ids().collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(registry.len(), ids.len());
+        assert_eq!(registry.iter().count(), ids.len());
+    }
+}
@@ -0,0 +1,58 @@
+//! Randomness of the rules. Everything random in a match goes through the
+//! [`MatchRng`] stored in the [`MatchState`](crate::MatchState), so a match plays
+//! out the same from the same seed and the same actions, in tests as in replays.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// SplitMix64: small, fast and defined down to the bit, unlike the generators
+/// of `rand` that may change between versions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MatchRng {
+    state: u64,
+}
+
+impl MatchRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, `bound` must not be zero.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "empty range");
+        let bound = bound as u64;
+        // multiply and keep the high half, rejecting the few values that would skew the result
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let wide = self.next_u64() as u128 * bound as u128;
+            if wide as u64 >= threshold {
+                return (wide >> 64) as usize;
+            }
+        }
+    }
+
+    /// Fisher-Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// A fresh seed for a new match. Log it to be able to replay the match.
+pub fn random_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    MatchRng::new(now.as_nanos() as u64).next_u64()
+}
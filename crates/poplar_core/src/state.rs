@@ -0,0 +1,1815 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ability::{Condition, Effect, Trigger},
+    action::{Action, EndReason, MatchEvent},
+    ball::{BallId, BallState, ServeRule, Table},
+    ball_kind::{BallKindId, BallKinds},
+    card::{Buff, CardId, CardInfo, CardInstance, CardInstanceId, CardType, Keyword},
+    checksum::Checksum,
+    deck::Deck,
+    error::{RuleError, UnknownCard},
+    paddle::PaddleState,
+    player::{PlayerId, PlayerState, Seat, Zone},
+    registry::CardRegistry,
+    rng::MatchRng,
+};
+
+/// The whole state of one match.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy::ecs::system::Resource))]
+pub struct MatchState {
+    players: [PlayerState; 2],
+    /// every ball in play, in the order they were served
+    pub balls: Vec<BallState>,
+    paddles: [PaddleState; 2],
+    pub table: Table,
+    pub ball_kinds: BallKinds,
+    pub tick: u64,
+    next_instance: u32,
+    next_ball: u32,
+    /// `0` in a [view](Self::view_for)
+    seed: u64,
+    /// only the authoritative side shuffles and serves, a view has none
+    rng: Option<MatchRng>,
+    result: Option<MatchResult>,
+}
+
+/// How a match ended, `winner` is `None` for a draw.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchResult {
+    pub winner: Option<Seat>,
+    pub reason: EndReason,
+}
+
+// what a step of the simulation did to the balls
+#[derive(Default)]
+struct Moved {
+    /// sent back by a paddle
+    returned: Vec<BallId>,
+    /// ran into a card on the battlefield
+    struck: Vec<(BallId, CardInstanceId)>,
+    /// got past the player in that seat
+    goals: Vec<(BallId, Seat)>,
+}
+
+impl MatchState {
+    pub const STARTING_HAND: usize = 3;
+    /// card effects don't put more balls than this into play
+    pub const MAX_BALLS: usize = 4;
+    /// ticks in a second, on the server as on the clients
+    pub const TICK_RATE: u32 = 60;
+    /// what every tick advances the simulation by, never the time a frame took
+    pub const TIMESTEP: f32 = 1.0 / Self::TICK_RATE as f32;
+
+    /// Builds a match from the two decklists and shuffles both decks.
+    /// The same decklists and `seed` always give the same match.
+    pub fn new(
+        registry: &CardRegistry,
+        decks: [&[CardId]; 2],
+        seed: u64,
+    ) -> Result<Self, UnknownCard> {
+        let mut next_instance = 0;
+        let [deck_one, deck_two] = decks;
+        let mut instantiate = |seat: Seat, deck: &[CardId]| {
+            deck.iter()
+                .map(|id| {
+                    next_instance += 1;
+                    let info = registry.get(id)?.clone();
+                    Ok(CardInstance::new(CardInstanceId(next_instance), seat, info))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let mut rng = MatchRng::new(seed);
+        let mut deck_one = Deck::new(instantiate(Seat::One, deck_one)?);
+        let mut deck_two = Deck::new(instantiate(Seat::Two, deck_two)?);
+        deck_one.shuffle(&mut rng);
+        deck_two.shuffle(&mut rng);
+        let players = [
+            PlayerState::new(Seat::One, deck_one),
+            PlayerState::new(Seat::Two, deck_two),
+        ];
+        Ok(Self {
+            players,
+            balls: Vec::new(),
+            paddles: Seat::ALL.map(PaddleState::new),
+            table: Table::default(),
+            ball_kinds: BallKinds::default(),
+            tick: 0,
+            next_instance,
+            next_ball: 0,
+            seed,
+            rng: Some(rng),
+            result: None,
+        })
+    }
+
+    /// Plays with the ball kinds of `balls.ron` instead of the standard ball alone.
+    pub fn with_ball_kinds(mut self, ball_kinds: BallKinds) -> Self {
+        self.ball_kinds = ball_kinds;
+        self
+    }
+
+    /// Serves after a goal by `serve_rule` instead of letting the loser serve.
+    pub fn with_serve_rule(mut self, serve_rule: ServeRule) -> Self {
+        self.table.serve_rule = serve_rule;
+        self
+    }
+
+    /// What the match was created with, enough to replay it given the same actions.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn rng(&mut self) -> &mut MatchRng {
+        self.rng
+            .as_mut()
+            .expect("only the authoritative match shuffles and serves")
+    }
+
+    /// What the player in `seat` may know of the match: the opponent's hand and both decks are hidden
+    /// and there is no rng, so what comes next can't be worked out from it.
+    pub fn view_for(&self, seat: Seat) -> Self {
+        let mut view = self.clone();
+        for player in view.players.iter_mut() {
+            player.deck.hide();
+            if player.seat != seat {
+                player.hand.iter_mut().for_each(CardInstance::hide);
+            }
+        }
+        view.seed = 0;
+        view.rng = None;
+        view
+    }
+
+    /// `events`, which this match just went through, as the player in `seat` gets them:
+    /// every card leaving a zone hidden from them is revealed first.
+    pub fn events_for(&self, seat: Seat, events: &[MatchEvent]) -> Vec<MatchEvent> {
+        let hidden =
+            |owner: Seat, zone: Zone| zone == Zone::Deck || (zone == Zone::Hand && owner != seat);
+        let mut seen = Vec::with_capacity(events.len());
+        for event in events {
+            let revealed = match *event {
+                MatchEvent::CardDrawn { seat: owner, card } if owner == seat => Some(card),
+                MatchEvent::CardPlayed {
+                    seat: owner, card, ..
+                }
+                | MatchEvent::SpellCast { seat: owner, card }
+                | MatchEvent::CardAttached {
+                    seat: owner, card, ..
+                }
+                | MatchEvent::FieldPlayed { seat: owner, card }
+                    if hidden(owner, Zone::Hand) =>
+                {
+                    Some(card)
+                }
+                MatchEvent::CardBuried {
+                    seat: owner,
+                    card,
+                    from,
+                }
+                | MatchEvent::CardExiled {
+                    seat: owner,
+                    card,
+                    from,
+                } if hidden(owner, from) => Some(card),
+                _ => None,
+            };
+            if let Some(instance) = revealed.and_then(|card| self.card(card)) {
+                seen.push(MatchEvent::CardRevealed {
+                    card: instance.id,
+                    id: instance.info.id.clone(),
+                });
+            }
+            seen.push(event.clone());
+        }
+        seen
+    }
+
+    /// Looks the definition of every card up again after the state was deserialized,
+    /// only card ids travel over the network.
+    pub fn link(&mut self, registry: &CardRegistry) -> Result<(), UnknownCard> {
+        fn link_card(card: &mut CardInstance, registry: &CardRegistry) -> Result<(), UnknownCard> {
+            if card.is_hidden() {
+                return Ok(());
+            }
+            card.info = registry.get(&card.info.id)?.clone();
+            card.attached
+                .iter_mut()
+                .try_for_each(|equipment| link_card(equipment, registry))
+        }
+        self.players.iter_mut().try_for_each(|player| {
+            player
+                .hand
+                .iter_mut()
+                .chain(player.battlefield.iter_mut().flatten())
+                .chain(player.field.iter_mut())
+                .chain(player.deck.iter_mut())
+                .chain(player.graveyard.iter_mut())
+                .chain(player.exile.iter_mut())
+                .try_for_each(|card| link_card(card, registry))
+        })
+    }
+
+    /// Draws the opening hands and serves the first ball.
+    pub fn deal(&mut self) -> Vec<MatchEvent> {
+        let mut events = Vec::new();
+        for seat in Seat::ALL {
+            for _ in 0..Self::STARTING_HAND {
+                if let Ok(mut drawn) = self.perform(seat, Action::DrawCard) {
+                    events.append(&mut drawn);
+                }
+            }
+        }
+        events.push(self.serve(Seat::One, BallKindId::standard()));
+        events
+    }
+
+    pub fn player(&self, seat: Seat) -> &PlayerState {
+        &self.players[seat.index()]
+    }
+
+    pub fn player_mut(&mut self, seat: Seat) -> &mut PlayerState {
+        &mut self.players[seat.index()]
+    }
+
+    pub fn paddle(&self, seat: Seat) -> &PaddleState {
+        &self.paddles[seat.index()]
+    }
+
+    pub fn paddle_mut(&mut self, seat: Seat) -> &mut PaddleState {
+        &mut self.paddles[seat.index()]
+    }
+
+    /// How the player in `seat` steers their paddle, see [`PaddleState::direction`].
+    pub fn steer(&mut self, seat: Seat, direction: i8) {
+        self.paddle_mut(seat).direction = direction.clamp(-1, 1);
+    }
+
+    /// Slides both paddles the way they are steered.
+    pub fn move_paddles(&mut self, dt: f32) {
+        let table = self.table;
+        for paddle in self.paddles.iter_mut() {
+            paddle.step(dt, &table);
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerState> {
+        self.players.iter()
+    }
+
+    pub fn seat_of(&self, id: PlayerId) -> Option<Seat> {
+        self.players
+            .iter()
+            .find(|player| player.id == id)
+            .map(|player| player.seat)
+    }
+
+    /// Looks a card up wherever it currently is, equipment included.
+    pub fn card(&self, id: CardInstanceId) -> Option<&CardInstance> {
+        self.players.iter().find_map(|player| {
+            player
+                .hand
+                .iter()
+                .chain(player.battlefield.iter().flatten())
+                .chain(
+                    player
+                        .battlefield
+                        .iter()
+                        .flatten()
+                        .flat_map(|host| host.attached.iter()),
+                )
+                .chain(player.field.iter())
+                .chain(player.deck.iter())
+                .chain(player.graveyard.iter())
+                .chain(player.exile.iter())
+                .find(|card| card.id == id)
+        })
+    }
+
+    fn card_mut(&mut self, id: CardInstanceId) -> Option<&mut CardInstance> {
+        self.players.iter_mut().find_map(|player| {
+            player
+                .hand
+                .iter_mut()
+                .chain(player.battlefield.iter_mut().flatten())
+                .chain(player.field.iter_mut())
+                .chain(player.deck.iter_mut())
+                .chain(player.graveyard.iter_mut())
+                .chain(player.exile.iter_mut())
+                .find(|card| card.id == id)
+        })
+    }
+
+    fn on_battlefield(&self, id: CardInstanceId) -> Option<(Seat, usize)> {
+        self.players
+            .iter()
+            .find_map(|player| player.battlefield_index(id).map(|slot| (player.seat, slot)))
+    }
+
+    /// Validates and performs `action` on behalf of `seat`, ending the match if that decided it.
+    pub fn apply(&mut self, seat: Seat, action: Action) -> Result<Vec<MatchEvent>, RuleError> {
+        if self.is_over() {
+            return Err(RuleError::MatchOver);
+        }
+        let mut events = self.perform(seat, action)?;
+        events.append(&mut self.check_end());
+        Ok(events)
+    }
+
+    fn perform(&mut self, seat: Seat, action: Action) -> Result<Vec<MatchEvent>, RuleError> {
+        let player = self.player_mut(seat);
+        match action {
+            Action::DrawCard => {
+                if player.hand.len() >= PlayerState::HAND_SIZE {
+                    return Err(RuleError::HandFull(seat));
+                }
+                let card = player.deck.draw_top().ok_or(RuleError::DeckEmpty(seat))?;
+                let id = card.id;
+                player.hand.push(card);
+                Ok(vec![MatchEvent::CardDrawn { seat, card: id }])
+            }
+            Action::PlayCard { card, slot } => {
+                let hand_index = player.hand_index(card).ok_or(RuleError::NotInHand(card))?;
+                let cost = player.hand[hand_index].stats().cost.max(0) as u32;
+                if cost > player.energy {
+                    return Err(RuleError::NotEnoughEnergy {
+                        cost,
+                        energy: player.energy,
+                    });
+                }
+                let mut events = match player.hand[hand_index].info.card_type {
+                    CardType::Creature => self.play_creature(seat, hand_index, slot)?,
+                    CardType::Spell => self.cast_spell(seat, hand_index),
+                    CardType::Equipment => self.equip(seat, hand_index, slot)?,
+                    CardType::Field => self.play_field(seat, hand_index),
+                };
+                // only paid once the card made it into play
+                let player = self.player_mut(seat);
+                player.energy -= cost;
+                events.insert(
+                    0,
+                    MatchEvent::EnergyChanged {
+                        seat,
+                        energy: player.energy,
+                    },
+                );
+                Ok(events)
+            }
+            Action::MoveCard { card, slot } => {
+                let from = player
+                    .battlefield_index(card)
+                    .ok_or(RuleError::NotOnBattlefield(card))?;
+                match player.battlefield.get(slot) {
+                    None => return Err(RuleError::NoSuchSlot(slot)),
+                    Some(Some(_)) => return Err(RuleError::SlotOccupied(slot)),
+                    Some(None) => {}
+                }
+                if let Some(instance) = &player.battlefield[from] {
+                    if instance.has_keyword(Keyword::Rooted) {
+                        return Err(RuleError::Rooted(card));
+                    }
+                    if !instance.is_ready() {
+                        return Err(RuleError::OnCooldown(card));
+                    }
+                }
+                player.battlefield.swap(from, slot);
+                Ok(vec![MatchEvent::CardMoved {
+                    seat,
+                    card,
+                    from,
+                    to: slot,
+                }])
+            }
+            Action::Concede => Ok(self.end(Some(seat.opponent()), EndReason::Concession)),
+        }
+    }
+
+    pub fn result(&self) -> Option<MatchResult> {
+        self.result
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Ends the match unless it already is, nothing can be done in it afterwards.
+    pub fn end(&mut self, winner: Option<Seat>, reason: EndReason) -> Vec<MatchEvent> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        let event = MatchEvent::MatchEnded { winner, reason };
+        self.apply_event(&event);
+        vec![event]
+    }
+
+    /// Ends the match once a player ran out of health or out of cards, both at once is a draw.
+    pub fn check_end(&mut self) -> Vec<MatchEvent> {
+        for reason in [EndReason::Health, EndReason::DeckOut] {
+            let lost = Seat::ALL.map(|seat| {
+                let player = self.player(seat);
+                match reason {
+                    EndReason::Health => player.is_dead(),
+                    _ => player.is_out_of_cards(),
+                }
+            });
+            let winner = match lost {
+                [false, false] => continue,
+                [true, true] => None,
+                [true, false] => Some(Seat::Two),
+                [false, true] => Some(Seat::One),
+            };
+            return self.end(winner, reason);
+        }
+        Vec::new()
+    }
+
+    fn play_creature(
+        &mut self,
+        seat: Seat,
+        hand_index: usize,
+        slot: usize,
+    ) -> Result<Vec<MatchEvent>, RuleError> {
+        let player = self.player_mut(seat);
+        match player.battlefield.get(slot) {
+            None => return Err(RuleError::NoSuchSlot(slot)),
+            Some(Some(_)) => return Err(RuleError::SlotOccupied(slot)),
+            Some(None) => {}
+        }
+        let mut instance = player.hand.remove(hand_index);
+        instance.enter_battlefield();
+        player.battlefield[slot] = Some(instance.clone());
+        let mut events = vec![MatchEvent::CardPlayed {
+            seat,
+            card: instance.id,
+            slot,
+        }];
+        events.append(&mut self.resolve(&instance, None, |trigger| *trigger == Trigger::Play));
+        Ok(events)
+    }
+
+    fn cast_spell(&mut self, seat: Seat, hand_index: usize) -> Vec<MatchEvent> {
+        let instance = self.player_mut(seat).hand.remove(hand_index);
+        let mut events = vec![MatchEvent::SpellCast {
+            seat,
+            card: instance.id,
+        }];
+        events.append(&mut self.bury(instance.clone(), Zone::Hand));
+        events.append(&mut self.resolve(&instance, None, |trigger| *trigger == Trigger::Play));
+        events
+    }
+
+    fn equip(
+        &mut self,
+        seat: Seat,
+        hand_index: usize,
+        slot: usize,
+    ) -> Result<Vec<MatchEvent>, RuleError> {
+        let player = self.player_mut(seat);
+        let host = match player.battlefield.get(slot) {
+            None => return Err(RuleError::NoSuchSlot(slot)),
+            Some(None) => return Err(RuleError::NothingToEquip(slot)),
+            Some(Some(host)) => host.id,
+        };
+        let instance = player.hand.remove(hand_index);
+        if let Some(Some(host)) = player.battlefield.get_mut(slot) {
+            host.attached.push(instance.clone());
+        }
+        let mut events = vec![MatchEvent::CardAttached {
+            seat,
+            card: instance.id,
+            slot,
+            host,
+        }];
+        events.append(&mut self.resolve(&instance, None, |trigger| *trigger == Trigger::Play));
+        // equipment can lower health as well
+        events.append(&mut self.destroy_if_dead(host));
+        Ok(events)
+    }
+
+    fn play_field(&mut self, seat: Seat, hand_index: usize) -> Vec<MatchEvent> {
+        let player = self.player_mut(seat);
+        let instance = player.hand.remove(hand_index);
+        let replaced = player.field.replace(instance.clone());
+        let mut events = vec![MatchEvent::FieldPlayed {
+            seat,
+            card: instance.id,
+        }];
+        if let Some(replaced) = replaced {
+            // the table forgets what the old field did to it
+            let bonus: isize = replaced
+                .info
+                .abilities
+                .iter()
+                .filter_map(|ability| match (ability.trigger, &ability.effect) {
+                    (Trigger::Play, Effect::BallSpeed(percent)) => Some(*percent),
+                    _ => None,
+                })
+                .sum();
+            if bonus != 0 {
+                events.push(self.change_ball_speed(-bonus));
+            }
+            events.append(&mut self.bury(replaced, Zone::Field));
+        }
+        events.append(&mut self.resolve(&instance, None, |trigger| *trigger == Trigger::Play));
+        events
+    }
+
+    pub fn shuffle_deck(&mut self, seat: Seat) -> Vec<MatchEvent> {
+        let event = MatchEvent::DeckShuffled { seat };
+        self.apply_event(&event);
+        vec![event]
+    }
+
+    fn change_ball_speed(&mut self, percent: isize) -> MatchEvent {
+        let event = MatchEvent::TableChanged {
+            ball_speed_bonus: self.table.ball_speed_bonus + percent,
+        };
+        self.apply_event(&event);
+        event
+    }
+
+    fn change_paddle(&mut self, seat: Seat, width: isize, speed: isize) -> MatchEvent {
+        let paddle = self.paddle(seat);
+        let event = MatchEvent::PaddleChanged {
+            seat,
+            width_bonus: paddle.width_bonus + width,
+            speed_bonus: paddle.speed_bonus + speed,
+        };
+        self.apply_event(&event);
+        event
+    }
+
+    /// [`apply_event`](Self::apply_event) on a [view](Self::view_for): a revealed card is looked up
+    /// in `registry` right away, so the events after it see the whole card.
+    pub fn apply_seen_event(
+        &mut self,
+        event: &MatchEvent,
+        registry: &CardRegistry,
+    ) -> Result<(), UnknownCard> {
+        self.apply_event(event);
+        if let MatchEvent::CardRevealed { card, ref id } = *event {
+            if let Some(card) = self.card_mut(card) {
+                card.info = registry.get(id)?.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays an event the authoritative side already validated, e.g. one received from the server.
+    pub fn apply_event(&mut self, event: &MatchEvent) {
+        match *event {
+            MatchEvent::CardDrawn { seat, card } => {
+                let player = self.player_mut(seat);
+                if let Some(card) = player.deck.remove(card) {
+                    player.hand.push(card);
+                }
+            }
+            MatchEvent::CardPlayed { seat, card, slot } => {
+                let player = self.player_mut(seat);
+                if let (Some(hand_index), Some(None)) =
+                    (player.hand_index(card), player.battlefield.get(slot))
+                {
+                    let mut instance = player.hand.remove(hand_index);
+                    instance.enter_battlefield();
+                    player.battlefield[slot] = Some(instance);
+                }
+            }
+            MatchEvent::CardMoved { seat, from, to, .. } => {
+                let player = self.player_mut(seat);
+                if from < player.battlefield.len() && to < player.battlefield.len() {
+                    player.battlefield.swap(from, to);
+                }
+            }
+            MatchEvent::PlayerHurt { seat, health, .. } => {
+                self.player_mut(seat).health = health;
+            }
+            MatchEvent::EnergyChanged { seat, energy } => {
+                self.player_mut(seat).energy = energy;
+            }
+            MatchEvent::CardBuffed { card, stat, amount } => {
+                if let Some(card) = self.card_mut(card) {
+                    card.buff(Buff { stat, amount });
+                }
+            }
+            MatchEvent::CooldownStarted { card } => {
+                if let Some(card) = self.card_mut(card) {
+                    card.restart_cooldown();
+                }
+            }
+            MatchEvent::CardDamaged { card, damage, .. } => {
+                if let Some(card) = self.card_mut(card) {
+                    card.damage += damage;
+                }
+            }
+            MatchEvent::CardDestroyed { seat, card, slot } => {
+                let player = self.player_mut(seat);
+                if player.battlefield_index(card) == Some(slot) {
+                    if let Some(instance) = player.battlefield[slot].take() {
+                        player.bury(instance);
+                    }
+                }
+            }
+            MatchEvent::CardBuried { seat, card, from } => {
+                // usually the event that made the card leave buried it already
+                let player = self.player_mut(seat);
+                if player.zone_of(card) == Some(from) {
+                    if let Some((_, instance)) = player.take(card) {
+                        player.bury(instance);
+                    }
+                }
+            }
+            MatchEvent::CardExiled { seat, card, from } => {
+                let player = self.player_mut(seat);
+                if player.zone_of(card) == Some(from) {
+                    if let Some((_, instance)) = player.take(card) {
+                        player.exile(instance);
+                    }
+                }
+            }
+            MatchEvent::CardReturned {
+                seat,
+                card,
+                from,
+                to,
+            } => {
+                let player = self.player_mut(seat);
+                if player.zone_of(card) == Some(from) {
+                    if let Some((_, instance)) = player.take(card) {
+                        match to {
+                            Zone::Deck => player.deck.insert(0, instance),
+                            _ => player.hand.push(instance),
+                        }
+                    }
+                }
+            }
+            MatchEvent::SpellCast { seat, card } => {
+                let player = self.player_mut(seat);
+                if let Some(hand_index) = player.hand_index(card) {
+                    let instance = player.hand.remove(hand_index);
+                    player.bury(instance);
+                }
+            }
+            MatchEvent::CardAttached {
+                seat, card, slot, ..
+            } => {
+                let player = self.player_mut(seat);
+                if let Some(hand_index) = player.hand_index(card) {
+                    if let Some(Some(_)) = player.battlefield.get(slot) {
+                        let instance = player.hand.remove(hand_index);
+                        if let Some(Some(host)) = player.battlefield.get_mut(slot) {
+                            host.attached.push(instance);
+                        }
+                    }
+                }
+            }
+            MatchEvent::FieldPlayed { seat, card } => {
+                let player = self.player_mut(seat);
+                if let Some(hand_index) = player.hand_index(card) {
+                    let instance = player.hand.remove(hand_index);
+                    if let Some(replaced) = player.field.replace(instance) {
+                        player.bury(replaced);
+                    }
+                }
+            }
+            MatchEvent::DeckShuffled { seat } => {
+                let MatchState { players, rng, .. } = self;
+                let deck = &mut players[seat.index()].deck;
+                match rng {
+                    Some(rng) => deck.shuffle(rng),
+                    None => deck.hide(),
+                }
+            }
+            MatchEvent::CardRevealed { card, ref id } => {
+                if let Some(card) = self.card_mut(card).filter(|card| card.is_hidden()) {
+                    card.info = CardInfo::unlinked(id.clone());
+                }
+            }
+            MatchEvent::TableChanged { ball_speed_bonus } => {
+                let before = self.table.ball_speed();
+                self.table.ball_speed_bonus = ball_speed_bonus;
+                for ball in self.balls.iter_mut() {
+                    ball.velocity *= self.table.ball_speed() / before;
+                }
+            }
+            MatchEvent::BallSpawned {
+                ball,
+                seat,
+                ref kind,
+                angle,
+            } => {
+                let mut served = BallState::served_by(ball, seat, self.ball_kinds.get(kind));
+                served.deflect(angle);
+                served.velocity *= self.table.ball_speed();
+                self.next_ball = self.next_ball.max(ball.0);
+                self.balls.push(served);
+            }
+            MatchEvent::BallLost { ball } => self.balls.retain(|state| state.id != ball),
+            MatchEvent::BallRallied { ball, rally } => {
+                if let Some(ball) = self.ball_mut(ball) {
+                    if ball.rally < rally {
+                        ball.rally_hit();
+                        ball.rally = rally;
+                    }
+                }
+            }
+            MatchEvent::BallSpedUp { ball, percent } => {
+                if let Some(ball) = self.ball_mut(ball) {
+                    ball.speed_up(percent);
+                }
+            }
+            MatchEvent::BallDeflected { ball, degrees } => {
+                if let Some(ball) = self.ball_mut(ball) {
+                    ball.deflect(degrees);
+                }
+            }
+            MatchEvent::BallEmpowered { ball, power } => {
+                if let Some(ball) = self.ball_mut(ball) {
+                    ball.power = power;
+                }
+            }
+            MatchEvent::PaddleChanged {
+                seat,
+                width_bonus,
+                speed_bonus,
+            } => {
+                let paddle = self.paddle_mut(seat);
+                paddle.width_bonus = width_bonus;
+                paddle.speed_bonus = speed_bonus;
+            }
+            MatchEvent::MatchEnded { winner, reason } => {
+                self.result = Some(MatchResult { winner, reason });
+            }
+        }
+    }
+
+    pub fn ball(&self, ball: BallId) -> Option<&BallState> {
+        self.balls.iter().find(|state| state.id == ball)
+    }
+
+    pub fn ball_mut(&mut self, ball: BallId) -> Option<&mut BallState> {
+        self.balls.iter_mut().find(|state| state.id == ball)
+    }
+
+    /// The ball an ability is about: the one that hit the card, every ball in play otherwise.
+    fn balls_for(&self, ball: Option<BallId>) -> impl Iterator<Item = &BallState> {
+        self.balls
+            .iter()
+            .filter(move |state| ball.is_none() || ball == Some(state.id))
+    }
+
+    /// Fires the `ball_hit` abilities of a card on the battlefield, unless it is still cooling down.
+    /// The cooldown starts over once they fired, their ball effects go to `ball`.
+    pub fn ball_hit(&mut self, card: CardInstanceId, ball: BallId) -> Vec<MatchEvent> {
+        match self.card(card) {
+            Some(instance) if self.on_battlefield(card).is_some() && instance.is_ready() => {
+                let instance = instance.clone();
+                let event = MatchEvent::CooldownStarted { card };
+                self.apply_event(&event);
+                let mut events = vec![event];
+                events.append(&mut self.resolve(&instance, Some(ball), |trigger| {
+                    *trigger == Trigger::BallHit
+                }));
+                events
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// A ball ran into a card on the battlefield. Its `ball_hit` abilities get to change the ball first,
+    /// then the card takes the power the ball came in with as damage.
+    pub fn strike(&mut self, card: CardInstanceId, ball: BallId) -> Vec<MatchEvent> {
+        let Some(power) = self.ball(ball).map(|state| state.power) else {
+            return Vec::new();
+        };
+        if self.on_battlefield(card).is_none() {
+            return Vec::new();
+        }
+        let mut events: Vec<_> = self.rally(ball).into_iter().collect();
+        events.append(&mut self.ball_hit(card, ball));
+        events.append(&mut self.damage_card(card, power as isize));
+        events
+    }
+
+    /// Hits a card on the battlefield, destroying it once its health runs out.
+    pub fn damage_card(&mut self, card: CardInstanceId, amount: isize) -> Vec<MatchEvent> {
+        let Some(instance) = self.card_mut(card) else {
+            return Vec::new();
+        };
+        let damage = instance.take_damage(amount);
+        let mut events = vec![MatchEvent::CardDamaged {
+            card,
+            damage,
+            health: instance.health().current,
+        }];
+        events.append(&mut self.destroy_if_dead(card));
+        events
+    }
+
+    fn destroy_if_dead(&mut self, card: CardInstanceId) -> Vec<MatchEvent> {
+        match self.card(card) {
+            Some(instance) if instance.is_dead() => self.destroy(card).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Removes a card from the battlefield and fires its `death` abilities.
+    pub fn destroy(&mut self, card: CardInstanceId) -> Result<Vec<MatchEvent>, RuleError> {
+        let (seat, slot) = self
+            .on_battlefield(card)
+            .ok_or(RuleError::NotOnBattlefield(card))?;
+        let instance = self.player_mut(seat).battlefield[slot]
+            .take()
+            .ok_or(RuleError::NotOnBattlefield(card))?;
+        let mut events = vec![MatchEvent::CardDestroyed { seat, card, slot }];
+        events.append(&mut self.bury(instance.clone(), Zone::Battlefield));
+        events.append(&mut self.resolve(&instance, None, |trigger| *trigger == Trigger::Death));
+        Ok(events)
+    }
+
+    /// Sends `card`, already taken out of `from`, to the graveyard of its owner along with its equipment.
+    fn bury(&mut self, card: CardInstance, from: Zone) -> Vec<MatchEvent> {
+        let seat = card.owner;
+        let id = card.id;
+        let equipment = self.player_mut(seat).bury(card);
+        equipment
+            .into_iter()
+            .map(|equipment| MatchEvent::CardBuried {
+                seat,
+                card: equipment,
+                from: Zone::Battlefield,
+            })
+            .chain([MatchEvent::CardBuried {
+                seat,
+                card: id,
+                from,
+            }])
+            .collect()
+    }
+
+    /// Removes a card from the game wherever it is, without firing its `death` abilities.
+    /// The equipment it carried goes to the graveyard.
+    pub fn exile(&mut self, card: CardInstanceId) -> Result<Vec<MatchEvent>, RuleError> {
+        let seat = self
+            .players
+            .iter()
+            .find(|player| player.zone_of(card).is_some())
+            .map(|player| player.seat)
+            .ok_or(RuleError::NoSuchCard(card))?;
+        let player = self.player_mut(seat);
+        let (from, instance) = player.take(card).ok_or(RuleError::NoSuchCard(card))?;
+        let equipment = player.exile(instance);
+        let mut events = vec![MatchEvent::CardExiled { seat, card, from }];
+        events.extend(
+            equipment
+                .into_iter()
+                .map(|equipment| MatchEvent::CardBuried {
+                    seat,
+                    card: equipment,
+                    from: Zone::Battlefield,
+                }),
+        );
+        Ok(events)
+    }
+
+    /// Brings a card of `seat` back from the graveyard or the exile, into the hand or on top of the deck.
+    pub fn return_card(
+        &mut self,
+        seat: Seat,
+        card: CardInstanceId,
+        to: Zone,
+    ) -> Result<Vec<MatchEvent>, RuleError> {
+        let player = self.player_mut(seat);
+        let from = match player.zone_of(card) {
+            Some(from @ (Zone::Graveyard | Zone::Exile)) => from,
+            _ => return Err(RuleError::NotInPile(card)),
+        };
+        match to {
+            Zone::Hand if player.hand.len() >= PlayerState::HAND_SIZE => {
+                return Err(RuleError::HandFull(seat))
+            }
+            Zone::Hand | Zone::Deck => {}
+            _ => return Err(RuleError::CantReturnTo(to)),
+        }
+        let event = MatchEvent::CardReturned {
+            seat,
+            card,
+            from,
+            to,
+        };
+        self.apply_event(&event);
+        Ok(vec![event])
+    }
+
+    /// Resolves the abilities of `card` whose trigger passes `fires`, in the order they are declared.
+    /// `ball` is the ball that set them off, if any.
+    fn resolve(
+        &mut self,
+        card: &CardInstance,
+        ball: Option<BallId>,
+        fires: impl Fn(&Trigger) -> bool,
+    ) -> Vec<MatchEvent> {
+        let mut events = Vec::new();
+        for ability in card
+            .info
+            .abilities
+            .iter()
+            .filter(|ability| fires(&ability.trigger))
+        {
+            let holds = match ability.condition {
+                Some(condition) => self.holds(card.owner, ball, condition),
+                None => true,
+            };
+            if holds {
+                events.append(&mut self.resolve_effect(card, ball, &ability.effect));
+            }
+        }
+        events
+    }
+
+    // the ball conditions hold if one of the balls the ability is about meets them
+    fn holds(&self, seat: Seat, ball: Option<BallId>, condition: Condition) -> bool {
+        match condition {
+            Condition::HealthBelow(health) => self.player(seat).health < health,
+            Condition::OpponentHealthBelow(health) => self.player(seat.opponent()).health < health,
+            Condition::HandBelow(cards) => self.player(seat).hand.len() < cards,
+            Condition::BallPowerAtLeast(power) => {
+                self.balls_for(ball).any(|state| state.power >= power)
+            }
+            Condition::GraveyardAtLeast(cards) => self.player(seat).graveyard.len() >= cards,
+            Condition::RallyAtLeast(hits) => self.balls_for(ball).any(|state| state.rally >= hits),
+        }
+    }
+
+    fn resolve_effect(
+        &mut self,
+        card: &CardInstance,
+        ball: Option<BallId>,
+        effect: &Effect,
+    ) -> Vec<MatchEvent> {
+        let seat = card.owner;
+        match effect {
+            Effect::Damage(damage) => vec![self.damage(seat.opponent(), *damage)],
+            Effect::Draw(cards) => {
+                let mut events = Vec::new();
+                for _ in 0..*cards {
+                    // drawing into a full hand or from an empty deck just fizzles
+                    match self.perform(seat, Action::DrawCard) {
+                        Ok(mut drawn) => events.append(&mut drawn),
+                        Err(_) => break,
+                    }
+                }
+                events
+            }
+            Effect::Buff { stat, amount } => {
+                let event = MatchEvent::CardBuffed {
+                    card: card.id,
+                    stat: *stat,
+                    amount: *amount,
+                };
+                self.apply_event(&event);
+                let mut events = vec![event];
+                // a negative health buff can be lethal
+                events.append(&mut self.destroy_if_dead(card.id));
+                events
+            }
+            Effect::SpawnBall(kind) if self.balls.len() < Self::MAX_BALLS => {
+                vec![self.serve(seat, kind.clone())]
+            }
+            Effect::SpawnBall(_) => Vec::new(),
+            Effect::Shift(slots) => {
+                let player = self.player_mut(seat);
+                let Some(from) = player.battlefield_index(card.id) else {
+                    return Vec::new();
+                };
+                if card.has_keyword(Keyword::Rooted) {
+                    return Vec::new();
+                }
+                let to = from as isize + *slots;
+                if to < 0 || !matches!(player.battlefield.get(to as usize), Some(None)) {
+                    return Vec::new();
+                }
+                let to = to as usize;
+                player.battlefield.swap(from, to);
+                vec![MatchEvent::CardMoved {
+                    seat,
+                    card: card.id,
+                    from,
+                    to,
+                }]
+            }
+            Effect::BallSpeed(percent) => vec![self.change_ball_speed(*percent)],
+            Effect::SpeedUp(percent) => self.change_balls(ball, |state| MatchEvent::BallSpedUp {
+                ball: state.id,
+                percent: *percent,
+            }),
+            Effect::Deflect(degrees) => {
+                self.change_balls(ball, |state| MatchEvent::BallDeflected {
+                    ball: state.id,
+                    degrees: *degrees,
+                })
+            }
+            Effect::Empower(power) => self.change_balls(ball, |state| MatchEvent::BallEmpowered {
+                ball: state.id,
+                power: state.power + power,
+            }),
+            Effect::PaddleWidth(percent) => vec![self.change_paddle(seat, *percent, 0)],
+            Effect::PaddleSpeed(percent) => vec![self.change_paddle(seat, 0, *percent)],
+            Effect::Recall => {
+                let creature = self
+                    .player(seat)
+                    .graveyard
+                    .search(|card| card.info.card_type == CardType::Creature)
+                    .next()
+                    .map(|card| card.id);
+                // a full hand or an empty graveyard just fizzles
+                creature
+                    .and_then(|creature| self.return_card(seat, creature, Zone::Hand).ok())
+                    .unwrap_or_default()
+            }
+        }
+    }
+
+    // one event for every ball an ability is about, applied right away
+    fn change_balls(
+        &mut self,
+        ball: Option<BallId>,
+        change: impl Fn(&BallState) -> MatchEvent,
+    ) -> Vec<MatchEvent> {
+        let events: Vec<_> = self.balls_for(ball).map(change).collect();
+        for event in events.iter() {
+            self.apply_event(event);
+        }
+        events
+    }
+
+    /// Hurts the player in `seat`, their health stops at zero.
+    pub fn damage(&mut self, seat: Seat, damage: i32) -> MatchEvent {
+        let player = self.player_mut(seat);
+        player.health = (player.health - damage).max(0);
+        MatchEvent::PlayerHurt {
+            seat,
+            damage,
+            health: player.health,
+        }
+    }
+
+    /// Puts a fresh ball of `kind` into play next to those already there, heading away from `seat`
+    /// at an angle the match rng picks.
+    pub fn serve(&mut self, seat: Seat, kind: BallKindId) -> MatchEvent {
+        let spread = BallState::SERVE_SPREAD;
+        let angle = self.rng().below(2 * spread as usize + 1) as i32 - spread;
+        let event = MatchEvent::BallSpawned {
+            ball: BallId(self.next_ball + 1),
+            seat,
+            kind,
+            angle,
+        };
+        self.apply_event(&event);
+        event
+    }
+
+    /// The ball was hit by a card or a paddle, it gets faster with every hit of the rally.
+    pub fn rally(&mut self, ball: BallId) -> Option<MatchEvent> {
+        let event = MatchEvent::BallRallied {
+            ball,
+            rally: self.ball(ball)?.rally + 1,
+        };
+        self.apply_event(&event);
+        Some(event)
+    }
+
+    // who serves once `ball` got past `seat`, as the serve rule of the table has it
+    fn next_server(&self, ball: &BallState, seat: Seat) -> Seat {
+        match self.table.serve_rule {
+            ServeRule::LoserServes => seat,
+            ServeRule::Alternate => ball.server.opponent(),
+        }
+    }
+
+    /// The ball got past `seat`: they take its power as damage and it leaves the table.
+    /// Once the last ball is gone a standard one is served.
+    pub fn goal(&mut self, ball: BallId, seat: Seat) -> Vec<MatchEvent> {
+        let Some(scored) = self.ball(ball) else {
+            return Vec::new();
+        };
+        let power = scored.power as i32;
+        let server = self.next_server(scored, seat);
+        let lost = MatchEvent::BallLost { ball };
+        self.apply_event(&lost);
+        let mut events = vec![self.damage(seat, power), lost];
+        if self.balls.is_empty() {
+            events.push(self.serve(server, BallKindId::standard()));
+        }
+        events
+    }
+
+    /// Advances the simulation by `dt` seconds: paddles, balls and the hits between them and the cards.
+    /// The server and offline matches run it, a tick of [`Self::TIMESTEP`] at a time
+    /// keeps every copy of the match in step.
+    pub fn tick(&mut self, dt: f32) -> Vec<MatchEvent> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.tick += 1;
+        let moved = self.simulate(dt);
+        let mut events: Vec<_> = moved
+            .returned
+            .into_iter()
+            .filter_map(|ball| self.rally(ball))
+            .collect();
+        for (ball, card) in moved.struck {
+            events.append(&mut self.strike(card, ball));
+        }
+        for (ball, seat) in moved.goals {
+            events.append(&mut self.goal(ball, seat));
+        }
+        self.cool_down(dt);
+        events.append(&mut self.regenerate(dt));
+        events.append(&mut self.tick_abilities(dt));
+        events.append(&mut self.check_end());
+        events
+    }
+
+    /// Moves the paddles and balls like [`Self::tick`] without playing any rules: nobody scores and no ability fires.
+    /// Clients run it ahead of the server, whose snapshots and events have the last word.
+    pub fn predict(&mut self, dt: f32) {
+        if self.is_over() {
+            return;
+        }
+        self.tick += 1;
+        let moved = self.simulate(dt);
+        let hit = moved.struck.into_iter().map(|(ball, _)| ball);
+        for ball in moved.returned.into_iter().chain(hit) {
+            // the server sends the same rally, applying it twice changes nothing
+            self.rally(ball);
+        }
+    }
+
+    // moves everything by `dt`, bouncing the balls off the paddles and the cards on the battlefield
+    fn simulate(&mut self, dt: f32) -> Moved {
+        self.move_paddles(dt);
+        let mut moved = Moved::default();
+        for ball in self.balls.iter_mut() {
+            if let Some(seat) = ball.step(dt, &self.table) {
+                moved.goals.push((ball.id, seat));
+                continue;
+            }
+            if self
+                .paddles
+                .iter()
+                .any(|paddle| paddle.bounce(ball, &self.table))
+            {
+                moved.returned.push(ball.id);
+                continue;
+            }
+            'cards: for player in self.players.iter() {
+                for (slot, card) in player.battlefield.iter().enumerate() {
+                    let Some(card) = card else {
+                        continue;
+                    };
+                    let center = self.table.slot_center(player.seat, slot);
+                    if ball.bounce_off(center, Table::SLOT_HALF_EXTENTS) {
+                        moved.struck.push((ball.id, card.id));
+                        break 'cards;
+                    }
+                }
+            }
+        }
+        moved
+    }
+
+    /// Fingerprint of everything that moves: the balls, the paddles and the tick they got to.
+    /// Two copies of a match that ran the same ticks from the same events give the same sum.
+    pub fn checksum(&self) -> u64 {
+        let mut sum = Checksum::new();
+        sum.u64(self.tick).u64(self.table.ball_speed_bonus as u64);
+        for ball in self.balls.iter() {
+            sum.u64(ball.id.0 as u64)
+                .vec2(ball.position)
+                .vec2(ball.velocity)
+                .u64(ball.power as u64)
+                .f32(ball.countdown)
+                .u64(ball.rally as u64)
+                .f32(ball.radius)
+                .f32(ball.bounciness);
+        }
+        for paddle in self.paddles.iter() {
+            sum.f32(paddle.x)
+                .u64(paddle.direction as u64)
+                .u64(paddle.width_bonus as u64)
+                .u64(paddle.speed_bonus as u64);
+        }
+        sum.finish()
+    }
+
+    /// Refills the energy of both players over time, a point at a time up to [`PlayerState::MAX_ENERGY`].
+    pub fn regenerate(&mut self, dt: f32) -> Vec<MatchEvent> {
+        let mut events = Vec::new();
+        for player in self.players.iter_mut() {
+            if player.energy >= PlayerState::MAX_ENERGY {
+                player.energy_progress = 0.0;
+                continue;
+            }
+            player.energy_progress += dt * PlayerState::ENERGY_RATE;
+            if player.energy_progress >= 1.0 {
+                let gained = player.energy_progress.floor();
+                player.energy_progress -= gained;
+                player.energy = (player.energy + gained as u32).min(PlayerState::MAX_ENERGY);
+                events.push(MatchEvent::EnergyChanged {
+                    seat: player.seat,
+                    energy: player.energy,
+                });
+            }
+        }
+        events
+    }
+
+    /// Advances the time every card spent on the battlefield and fires their `tick` abilities.
+    pub fn tick_abilities(&mut self, dt: f32) -> Vec<MatchEvent> {
+        let mut fired = Vec::new();
+        for player in self.players.iter_mut() {
+            for card in player.battlefield.iter_mut().flatten() {
+                let before = card.elapsed;
+                card.elapsed += dt;
+                if card
+                    .info
+                    .abilities
+                    .iter()
+                    .any(|ability| ability.trigger.ticks_between(before, card.elapsed))
+                {
+                    fired.push((card.clone(), before));
+                }
+            }
+        }
+        let mut events = Vec::new();
+        for (card, before) in fired {
+            let after = card.elapsed;
+            events.append(
+                &mut self.resolve(&card, None, |trigger| trigger.ticks_between(before, after)),
+            );
+        }
+        events
+    }
+
+    /// Counts down the cooldown of every card on the battlefield.
+    pub fn cool_down(&mut self, dt: f32) {
+        for player in self.players.iter_mut() {
+            for card in player.battlefield.iter_mut().flatten() {
+                card.cooldown = (card.cooldown - dt).max(0.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const CARDS: &str = "\
+soldier,soldier,士兵,creature,1,1,1,3,,soldier,,soldier.png
+giant,giant,巨人,creature,4,1,3,8,,giant,,giant.png
+sword,sword,剑,equipment,1,0,1,0,,sword,,sword.png
+";
+
+    fn soldiers(count: usize) -> MatchState {
+        let registry = testing::registry(CARDS);
+        testing::match_state(&registry, &vec!["soldier"; count], 7)
+    }
+
+    fn draw(state: &mut MatchState, seat: Seat) -> CardInstanceId {
+        match state.apply(seat, Action::DrawCard).unwrap()[..] {
+            [MatchEvent::CardDrawn { card, .. }] => card,
+            ref events => panic!("drew {:?}", events),
+        }
+    }
+
+    fn play(state: &mut MatchState, seat: Seat, slot: usize) -> CardInstanceId {
+        let card = draw(state, seat);
+        state.apply(seat, Action::PlayCard { card, slot }).unwrap();
+        card
+    }
+
+    #[test]
+    fn draw_card_into_full_hand() {
+        let mut state = soldiers(10);
+        for _ in 0..PlayerState::HAND_SIZE {
+            draw(&mut state, Seat::One);
+        }
+        assert_eq!(
+            state.apply(Seat::One, Action::DrawCard),
+            Err(RuleError::HandFull(Seat::One))
+        );
+        assert_eq!(state.player(Seat::One).hand.len(), PlayerState::HAND_SIZE);
+        assert_eq!(
+            state.player(Seat::One).deck.len(),
+            10 - PlayerState::HAND_SIZE
+        );
+    }
+
+    #[test]
+    fn draw_card_from_empty_deck() {
+        let mut state = soldiers(1);
+        draw(&mut state, Seat::One);
+        assert_eq!(
+            state.apply(Seat::One, Action::DrawCard),
+            Err(RuleError::DeckEmpty(Seat::One))
+        );
+        assert_eq!(state.player(Seat::One).hand.len(), 1);
+    }
+
+    #[test]
+    fn play_card_into_occupied_slot() {
+        let mut state = soldiers(3);
+        play(&mut state, Seat::One, 0);
+        let card = draw(&mut state, Seat::One);
+        assert_eq!(
+            state.apply(Seat::One, Action::PlayCard { card, slot: 0 }),
+            Err(RuleError::SlotOccupied(0))
+        );
+        // refused before anything was paid or moved
+        let player = state.player(Seat::One);
+        assert_eq!(player.hand_index(card), Some(0));
+        assert_eq!(player.energy, PlayerState::STARTING_ENERGY - 1);
+    }
+
+    #[test]
+    fn play_card_into_missing_slot() {
+        let mut state = soldiers(3);
+        let card = draw(&mut state, Seat::One);
+        let slot = PlayerState::BATTLEFIELD_SIZE;
+        assert_eq!(
+            state.apply(Seat::One, Action::PlayCard { card, slot }),
+            Err(RuleError::NoSuchSlot(slot))
+        );
+        assert_eq!(state.player(Seat::One).energy, PlayerState::STARTING_ENERGY);
+    }
+
+    #[test]
+    fn play_card_without_enough_energy() {
+        let registry = testing::registry(CARDS);
+        let mut state = testing::match_state(&registry, &["giant"], 7);
+        let card = draw(&mut state, Seat::One);
+        assert_eq!(
+            state.apply(Seat::One, Action::PlayCard { card, slot: 0 }),
+            Err(RuleError::NotEnoughEnergy {
+                cost: 4,
+                energy: PlayerState::STARTING_ENERGY,
+            })
+        );
+        state.regenerate(2.0);
+        assert!(state
+            .apply(Seat::One, Action::PlayCard { card, slot: 0 })
+            .is_ok());
+        assert_eq!(state.player(Seat::One).energy, 0);
+    }
+
+    #[test]
+    fn move_card_on_cooldown() {
+        let mut state = soldiers(3);
+        let card = play(&mut state, Seat::One, 0);
+        assert_eq!(
+            state.apply(Seat::One, Action::MoveCard { card, slot: 1 }),
+            Err(RuleError::OnCooldown(card))
+        );
+        state.cool_down(1.0);
+        assert!(state
+            .apply(Seat::One, Action::MoveCard { card, slot: 1 })
+            .is_ok());
+    }
+
+    // both paddles chasing back and forth, the same way in every copy
+    fn run(state: &mut MatchState, ticks: u64) -> Vec<u64> {
+        (0..ticks)
+            .map(|tick| {
+                let direction = if (tick / 45) % 2 == 0 { 1 } else { -1 };
+                state.steer(Seat::One, direction);
+                state.steer(Seat::Two, -direction);
+                state.tick(MatchState::TIMESTEP);
+                state.checksum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_ticks_same_checksums() {
+        let mut state = soldiers(10);
+        state.deal();
+        let mut copy = state.clone();
+        assert_eq!(run(&mut state, 600), run(&mut copy, 600));
+    }
+
+    #[test]
+    fn nudged_ball_changes_the_checksum() {
+        let mut state = soldiers(10);
+        state.deal();
+        let mut nudged = state.clone();
+        assert_eq!(run(&mut state, 60), run(&mut nudged, 60));
+        nudged.balls[0].position.x += 1e-6;
+        assert_ne!(state.checksum(), nudged.checksum());
+        // later ticks may serve both copies the same fresh ball, the tick right after the nudge can't
+        assert_ne!(run(&mut state, 1), run(&mut nudged, 1));
+    }
+
+    // `seat` let the only ball through, the next one is served by whoever the rule says
+    fn server_after_goal(serve_rule: ServeRule, seat: Seat) -> Seat {
+        let mut state = soldiers(10).with_serve_rule(serve_rule);
+        state.deal();
+        assert_eq!(state.balls[0].server, Seat::One);
+        let ball = state.balls[0].id;
+        state.goal(ball, seat);
+        assert_eq!(state.balls.len(), 1);
+        state.balls[0].server
+    }
+
+    #[test]
+    fn loser_serves() {
+        assert_eq!(
+            server_after_goal(ServeRule::LoserServes, Seat::One),
+            Seat::One
+        );
+        assert_eq!(
+            server_after_goal(ServeRule::LoserServes, Seat::Two),
+            Seat::Two
+        );
+    }
+
+    #[test]
+    fn alternate_serves() {
+        assert_eq!(
+            server_after_goal(ServeRule::Alternate, Seat::One),
+            Seat::Two
+        );
+        assert_eq!(
+            server_after_goal(ServeRule::Alternate, Seat::Two),
+            Seat::Two
+        );
+        // the serve changes hands every goal, even when the same player concedes twice
+        let mut state = soldiers(10).with_serve_rule(ServeRule::Alternate);
+        state.deal();
+        let mut servers = vec![state.balls[0].server];
+        for _ in 0..2 {
+            let ball = state.balls[0].id;
+            state.goal(ball, Seat::One);
+            servers.push(state.balls[0].server);
+        }
+        assert_eq!(servers, [Seat::One, Seat::Two, Seat::One]);
+    }
+
+    #[test]
+    fn move_card() {
+        let mut state = soldiers(3);
+        let card = play(&mut state, Seat::One, 0);
+        state.cool_down(1.0);
+        assert_eq!(
+            state.apply(Seat::One, Action::MoveCard { card, slot: 2 }),
+            Ok(vec![MatchEvent::CardMoved {
+                seat: Seat::One,
+                card,
+                from: 0,
+                to: 2,
+            }])
+        );
+        let player = state.player(Seat::One);
+        assert_eq!(player.battlefield_index(card), Some(2));
+        assert!(player.battlefield[0].is_none());
+
+        let other = play(&mut state, Seat::One, 0);
+        state.cool_down(1.0);
+        assert_eq!(
+            state.apply(
+                Seat::One,
+                Action::MoveCard {
+                    card: other,
+                    slot: 2
+                }
+            ),
+            Err(RuleError::SlotOccupied(2))
+        );
+        assert_eq!(
+            state.apply(Seat::Two, Action::MoveCard { card, slot: 1 }),
+            Err(RuleError::NotOnBattlefield(card))
+        );
+    }
+
+    #[test]
+    fn view_hides_what_the_player_cant_see() {
+        let registry = testing::registry(CARDS);
+        let deck = ["soldier", "giant", "soldier", "giant", "soldier", "giant"];
+        let mut state = testing::match_state(&registry, &deck, 7);
+        let reshuffled = testing::match_state(&registry, &deck, 8).view_for(Seat::One);
+        let view = state.view_for(Seat::One);
+        assert_eq!(view.seed(), 0);
+        for seat in Seat::ALL {
+            let deck = &view.player(seat).deck;
+            assert!(deck.iter().all(CardInstance::is_hidden));
+            // whatever the seed, the deck looks the same
+            assert_eq!(
+                format!("{:?}", deck),
+                format!("{:?}", reshuffled.player(seat).deck)
+            );
+        }
+        state.deal();
+        let view = state.view_for(Seat::One);
+        assert!(!view
+            .player(Seat::One)
+            .hand
+            .iter()
+            .any(CardInstance::is_hidden));
+        assert!(view
+            .player(Seat::Two)
+            .hand
+            .iter()
+            .all(CardInstance::is_hidden));
+    }
+
+    #[test]
+    fn view_follows_the_events_for_its_seat() {
+        let registry = testing::registry(CARDS);
+        let deck = ["soldier", "giant", "soldier", "giant", "soldier", "giant"];
+        let mut state = testing::match_state(&registry, &deck, 7);
+        let mut views = Seat::ALL.map(|seat| (seat, state.view_for(seat)));
+        let mut events = state.deal();
+        events.append(&mut state.apply(Seat::One, Action::DrawCard).unwrap());
+        let soldier = CardId::from("soldier");
+        let card = state
+            .player(Seat::Two)
+            .hand
+            .iter()
+            .find(|card| card.info.id == soldier)
+            .unwrap()
+            .id;
+        events.append(
+            &mut state
+                .apply(Seat::Two, Action::PlayCard { card, slot: 0 })
+                .unwrap(),
+        );
+        for (seat, view) in views.iter_mut() {
+            for event in state.events_for(*seat, &events) {
+                view.apply_seen_event(&event, &registry).unwrap();
+            }
+            assert_eq!(view.checksum(), state.checksum());
+        }
+        let [(_, one), (_, two)] = &views;
+        // the soldier Two played is out in the open, with the cooldown it came in with
+        let played = one.card(card).unwrap();
+        assert_eq!(played.info.id, soldier);
+        assert_eq!(played.cooldown, state.card(card).unwrap().cooldown);
+        assert!(!two.card(card).unwrap().is_hidden());
+        // each side knows its own hand and nothing of the other one
+        for (seat, view) in [(Seat::One, one), (Seat::Two, two)] {
+            let hand = &view.player(seat).hand;
+            assert_eq!(hand.len(), state.player(seat).hand.len());
+            assert!(!hand.iter().any(CardInstance::is_hidden));
+            let other = &view.player(seat.opponent()).hand;
+            assert!(other.iter().all(CardInstance::is_hidden));
+        }
+    }
+
+    fn in_hand(state: &MatchState, seat: Seat, id: &str) -> CardInstanceId {
+        let id = CardId::from(id);
+        state
+            .player(seat)
+            .hand
+            .iter()
+            .find(|card| card.info.id == id)
+            .unwrap()
+            .id
+    }
+
+    /// Two soldiers on the battlefield of One, the first one carrying a sword, and two giants in hand.
+    fn armed() -> (MatchState, [CardInstanceId; 3]) {
+        let registry = testing::registry(CARDS);
+        let deck = ["soldier", "soldier", "sword", "giant", "giant"];
+        let mut state = testing::match_state(&registry, &deck, 7);
+        for _ in 0..deck.len() {
+            draw(&mut state, Seat::One);
+        }
+        let play = |state: &mut MatchState, id, slot| {
+            let card = in_hand(state, Seat::One, id);
+            state
+                .apply(Seat::One, Action::PlayCard { card, slot })
+                .unwrap();
+            card
+        };
+        let first = play(&mut state, "soldier", 0);
+        let second = play(&mut state, "soldier", 1);
+        let sword = play(&mut state, "sword", 0);
+        (state, [first, second, sword])
+    }
+
+    fn graveyard(state: &MatchState, seat: Seat) -> Vec<CardInstanceId> {
+        state
+            .player(seat)
+            .graveyard
+            .iter()
+            .map(|card| card.id)
+            .collect()
+    }
+
+    #[test]
+    fn destroy_buries_in_order() {
+        let (mut state, [first, second, sword]) = armed();
+        let mut replay = state.clone();
+        let mut events = state.destroy(second).unwrap();
+        assert_eq!(
+            events,
+            [
+                MatchEvent::CardDestroyed {
+                    seat: Seat::One,
+                    card: second,
+                    slot: 1
+                },
+                MatchEvent::CardBuried {
+                    seat: Seat::One,
+                    card: second,
+                    from: Zone::Battlefield
+                },
+            ]
+        );
+        let mut more = state.destroy(first).unwrap();
+        assert_eq!(
+            more,
+            [
+                MatchEvent::CardDestroyed {
+                    seat: Seat::One,
+                    card: first,
+                    slot: 0
+                },
+                MatchEvent::CardBuried {
+                    seat: Seat::One,
+                    card: sword,
+                    from: Zone::Battlefield
+                },
+                MatchEvent::CardBuried {
+                    seat: Seat::One,
+                    card: first,
+                    from: Zone::Battlefield
+                },
+            ]
+        );
+        assert_eq!(graveyard(&state, Seat::One), [second, sword, first]);
+        assert_eq!(state.player(Seat::One).graveyard.top().unwrap().id, first);
+        // replaying the events buries them the same way
+        events.append(&mut more);
+        for event in &events {
+            replay.apply_event(event);
+        }
+        assert_eq!(graveyard(&replay, Seat::One), [second, sword, first]);
+    }
+
+    #[test]
+    fn exile_from_each_zone() {
+        let (mut state, [first, second, sword]) = armed();
+        let giant = in_hand(&state, Seat::One, "giant");
+        assert_eq!(
+            state.exile(giant),
+            Ok(vec![MatchEvent::CardExiled {
+                seat: Seat::One,
+                card: giant,
+                from: Zone::Hand
+            }])
+        );
+        // the equipment can't go along, it is buried
+        assert_eq!(
+            state.exile(first),
+            Ok(vec![
+                MatchEvent::CardExiled {
+                    seat: Seat::One,
+                    card: first,
+                    from: Zone::Battlefield
+                },
+                MatchEvent::CardBuried {
+                    seat: Seat::One,
+                    card: sword,
+                    from: Zone::Battlefield
+                },
+            ])
+        );
+        state.destroy(second).unwrap();
+        assert_eq!(
+            state.exile(second),
+            Ok(vec![MatchEvent::CardExiled {
+                seat: Seat::One,
+                card: second,
+                from: Zone::Graveyard
+            }])
+        );
+        let exile: Vec<_> = state
+            .player(Seat::One)
+            .exile
+            .iter()
+            .map(|card| card.id)
+            .collect();
+        assert_eq!(exile, [giant, first, second]);
+        assert_eq!(graveyard(&state, Seat::One), [sword]);
+        assert_eq!(
+            state.exile(second),
+            Ok(vec![MatchEvent::CardExiled {
+                seat: Seat::One,
+                card: second,
+                from: Zone::Exile
+            }])
+        );
+        assert_eq!(
+            state.exile(CardInstanceId(99)),
+            Err(RuleError::NoSuchCard(CardInstanceId(99)))
+        );
+    }
+
+    #[test]
+    fn return_card_to_hand_and_deck() {
+        let (mut state, [first, second, sword]) = armed();
+        state.destroy(first).unwrap();
+        state.exile(second).unwrap();
+        assert_eq!(
+            state.return_card(Seat::One, sword, Zone::Deck),
+            Ok(vec![MatchEvent::CardReturned {
+                seat: Seat::One,
+                card: sword,
+                from: Zone::Graveyard,
+                to: Zone::Deck
+            }])
+        );
+        assert_eq!(
+            state.player(Seat::One).deck.peek(1).next().unwrap().id,
+            sword
+        );
+        assert_eq!(
+            state.return_card(Seat::One, second, Zone::Hand),
+            Ok(vec![MatchEvent::CardReturned {
+                seat: Seat::One,
+                card: second,
+                from: Zone::Exile,
+                to: Zone::Hand
+            }])
+        );
+        assert_eq!(state.player(Seat::One).hand.last().unwrap().id, second);
+        assert_eq!(graveyard(&state, Seat::One), [first]);
+        assert_eq!(
+            state.return_card(Seat::One, second, Zone::Hand),
+            Err(RuleError::NotInPile(second))
+        );
+        assert_eq!(
+            state.return_card(Seat::One, first, Zone::Battlefield),
+            Err(RuleError::CantReturnTo(Zone::Battlefield))
+        );
+        assert_eq!(
+            state.return_card(Seat::Two, first, Zone::Hand),
+            Err(RuleError::NotInPile(first))
+        );
+    }
+
+    #[test]
+    fn return_card_into_full_hand() {
+        let mut state = soldiers(10);
+        for _ in 0..PlayerState::HAND_SIZE {
+            draw(&mut state, Seat::One);
+        }
+        let top = state.player(Seat::One).deck.peek(1).next().unwrap().id;
+        assert_eq!(
+            state.exile(top),
+            Ok(vec![MatchEvent::CardExiled {
+                seat: Seat::One,
+                card: top,
+                from: Zone::Deck
+            }])
+        );
+        assert_eq!(
+            state.return_card(Seat::One, top, Zone::Hand),
+            Err(RuleError::HandFull(Seat::One))
+        );
+        // nothing moved, the deck is still there to return to
+        assert_eq!(state.player(Seat::One).zone_of(top), Some(Zone::Exile));
+        assert_eq!(state.player(Seat::One).hand.len(), PlayerState::HAND_SIZE);
+        state.return_card(Seat::One, top, Zone::Deck).unwrap();
+        assert_eq!(state.player(Seat::One).deck.peek(1).next().unwrap().id, top);
+    }
+
+    #[test]
+    fn ball_strikes_a_slotted_card() {
+        let mut state = soldiers(10);
+        let card = play(&mut state, Seat::Two, 2);
+        state.serve(Seat::One, BallKindId::standard());
+        let ball = &mut state.balls[0];
+        ball.countdown = 0.0;
+        ball.velocity = glam::Vec2::new(0.0, 1.0);
+        let ball = ball.id;
+        let mut events = Vec::new();
+        for _ in 0..MatchState::TICK_RATE {
+            events.append(&mut state.tick(MatchState::TIMESTEP));
+        }
+        let rallied = MatchEvent::BallRallied { ball, rally: 1 };
+        let damaged = MatchEvent::CardDamaged {
+            card,
+            damage: 1,
+            health: 2,
+        };
+        assert!(events.contains(&rallied), "{:?}", events);
+        assert!(events.contains(&damaged), "{:?}", events);
+        // the soldier sent it back, past the empty slot of One
+        assert!(state.balls[0].velocity.y < 0.0);
+        assert_eq!(state.card(card).unwrap().damage, 1);
+    }
+}
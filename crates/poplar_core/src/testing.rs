@@ -0,0 +1,19 @@
+//! Small matches for the unit tests of the rules.
+
+use crate::{CardId, CardPool, CardRegistry, MatchState, PoolFormat};
+
+const HEADER: &str =
+    "id,name,name_zh,card_type,cost,cooldown,attack,health,keywords,desc,abilities,art\n";
+
+/// The cards of `rows`, written like the rows of `cards.csv` without the header.
+pub fn registry(rows: &str) -> CardRegistry {
+    let pool = CardPool::parse(PoolFormat::Csv, format!("{}{}", HEADER, rows).as_bytes());
+    assert!(pool.is_valid(), "{:?}", pool.errors());
+    CardRegistry::from(&pool)
+}
+
+/// A match where both players bring `deck`.
+pub fn match_state(registry: &CardRegistry, deck: &[&str], seed: u64) -> MatchState {
+    let deck: Vec<CardId> = deck.iter().copied().map(CardId::from).collect();
+    MatchState::new(registry, [&deck, &deck], seed).unwrap()
+}
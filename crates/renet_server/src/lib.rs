@@ -0,0 +1,439 @@
+//! The authoritative CardPong server.
+//!
+//! `main.rs` runs it headless, the game adds [`MatchServerPlugin`] to host a match itself.
+
+use bevy::prelude::*;
+use bevy_renet::renet::{
+    transport::{ServerAuthentication, ServerConfig},
+    RenetServer, ServerEvent,
+};
+use poplar_core::{
+    protocol::{
+        connection_config, decode, encode, ClientChannel, ClientMessage, LobbyEntry, PlayerInput,
+        ServerChannel, ServerMessage, Snapshot, PROTOCOL_ID, PROTOCOL_VERSION,
+    },
+    random_seed, BallKinds, CardId, CardRegistry, Decklist, EndReason, MatchEvent, MatchState,
+    PlayerId, Seat, ServeRule,
+};
+use renet::{transport::NetcodeServerTransport, ClientId};
+use std::time::SystemTime;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, UdpSocket},
+};
+
+/// Clients that passed the handshake and got a seat.
+#[derive(Debug, Default, Resource)]
+pub struct Lobby {
+    players: HashMap<ClientId, LobbyEntry>,
+    /// the checked decklists the players sent
+    decks: HashMap<ClientId, Vec<CardId>>,
+}
+
+impl Lobby {
+    fn free_seat(&self) -> Option<Seat> {
+        Seat::ALL
+            .into_iter()
+            .find(|seat| !self.players.values().any(|taken| taken.seat == *seat))
+    }
+
+    fn seat_of(&self, client_id: ClientId) -> Option<Seat> {
+        self.players.get(&client_id).map(|entry| entry.seat)
+    }
+
+    fn is_full_and_ready(&self) -> bool {
+        self.players.len() == Seat::ALL.len() && self.players.values().all(|entry| entry.ready)
+    }
+
+    /// After a match everyone has to get ready again for the next one.
+    fn unready(&mut self) {
+        for entry in self.players.values_mut() {
+            entry.ready = false;
+        }
+    }
+
+    fn entries(&self) -> Vec<LobbyEntry> {
+        let mut entries: Vec<LobbyEntry> = self.players.values().copied().collect();
+        entries.sort_by_key(|entry| entry.seat.index());
+        entries
+    }
+}
+
+/// The match as the server sees it. A hosting game has its own client side `MatchState` too.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct ServerMatch(pub MatchState);
+
+/// Inputs that arrived before the match got to their tick, by seat in the order they were sent.
+#[derive(Debug, Default, Resource)]
+struct PendingInputs {
+    inputs: [Vec<PlayerInput>; 2],
+    /// tick of the newest input each seat sent, clients keep repeating the ones before it
+    newest: [u64; 2],
+}
+
+impl PendingInputs {
+    /// an input further ahead than this is from a client whose clock ran off, it applies right away
+    const MAX_LEAD: u64 = MatchState::TICK_RATE as u64 * 2;
+}
+
+/// Binds `bind_addr`, clients have to connect through one of `public_addresses`.
+pub fn new_renet_server(
+    bind_addr: SocketAddr,
+    public_addresses: Vec<SocketAddr>,
+) -> Result<(RenetServer, NetcodeServerTransport), std::io::Error> {
+    renet_server_on(UdpSocket::bind(bind_addr)?, public_addresses)
+}
+
+/// Like [`new_renet_server`] on a `socket` bound already, e.g. to a port the system picked.
+pub fn renet_server_on(
+    socket: UdpSocket,
+    public_addresses: Vec<SocketAddr>,
+) -> Result<(RenetServer, NetcodeServerTransport), std::io::Error> {
+    let current_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let server_config = ServerConfig {
+        current_time,
+        max_clients: 2,
+        protocol_id: PROTOCOL_ID,
+        public_addresses,
+        authentication: ServerAuthentication::Unsecure,
+    };
+
+    let transport = NetcodeServerTransport::new(server_config, socket)?;
+    let server = RenetServer::new(connection_config());
+
+    Ok((server, transport))
+}
+
+/// Runs the match once a `RenetServer`, its transport and a `CardRegistry` are inserted.
+/// Matches play with the `BallKinds` resource if there is one, with the standard ball otherwise,
+/// and serve by the `ServeRule` resource if there is one.
+pub struct MatchServerPlugin;
+
+impl Plugin for MatchServerPlugin {
+    fn build(&self, app: &mut App) {
+        // the match only moves a whole tick at a time, however long the frames take
+        app.init_resource::<Lobby>()
+            .init_resource::<PendingInputs>()
+            .insert_resource(Time::<Fixed>::from_hz(MatchState::TICK_RATE as f64))
+            .add_systems(
+                Update,
+                (server_update_system, server_handshake, server_lobby)
+                    .chain()
+                    .run_if(resource_exists::<RenetServer>),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    server_receive_input,
+                    server_receive_actions,
+                    server_tick_match,
+                    server_sync_match,
+                    server_end_match,
+                )
+                    .chain()
+                    .run_if(resource_exists::<RenetServer>)
+                    .run_if(resource_exists::<ServerMatch>),
+            );
+    }
+}
+
+fn broadcast_lobby(lobby: &Lobby, server: &mut RenetServer) {
+    let message = encode(&ServerMessage::Lobby(lobby.entries()));
+    server.broadcast_message(ServerChannel::ServerMessages, message);
+}
+
+fn server_update_system(
+    mut server_events: EventReader<ServerEvent>,
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    mut server: ResMut<RenetServer>,
+    mut server_match: Option<ResMut<ServerMatch>>,
+) {
+    for event in server_events.read() {
+        match event {
+            ServerEvent::ClientConnected { client_id } => {
+                info!("Player {} connected.", client_id);
+            }
+            ServerEvent::ClientDisconnected { client_id, reason } => {
+                info!("Player {} disconnected: {}", client_id, reason);
+                lobby.decks.remove(client_id);
+                if let Some(entry) = lobby.players.remove(client_id) {
+                    // a match can't go on with an empty seat, whoever stayed wins it
+                    if let Some(match_state) = server_match.as_mut() {
+                        let events =
+                            match_state.end(Some(entry.seat.opponent()), EndReason::Disconnect);
+                        send_events(&lobby, &mut server, match_state, &events);
+                        lobby.unready();
+                    }
+                    commands.remove_resource::<ServerMatch>();
+                    let message = encode(&ServerMessage::PlayerDisconnected {
+                        id: client_id.raw(),
+                    });
+                    server.broadcast_message(ServerChannel::ServerMessages, message);
+                    broadcast_lobby(&lobby, &mut server);
+                }
+            }
+        }
+    }
+}
+
+// seats clients that speak our protocol version
+fn server_handshake(mut lobby: ResMut<Lobby>, mut server: ResMut<RenetServer>) {
+    for client_id in server.clients_id() {
+        if lobby.seat_of(client_id).is_some() {
+            continue;
+        }
+        let Some(message) = server.receive_message(client_id, ClientChannel::Command) else {
+            continue;
+        };
+        let refusal = match decode::<ClientMessage>(&message) {
+            Ok(ClientMessage::Hello { version }) if version == PROTOCOL_VERSION => {
+                match lobby.free_seat() {
+                    Some(seat) => {
+                        let id = client_id.raw();
+                        lobby.players.insert(
+                            client_id,
+                            LobbyEntry {
+                                id,
+                                seat,
+                                ready: false,
+                            },
+                        );
+                        let welcome = encode(&ServerMessage::Welcome { id, seat });
+                        server.send_message(client_id, ServerChannel::ServerMessages, welcome);
+                        let message = encode(&ServerMessage::PlayerConnected { id });
+                        server.broadcast_message(ServerChannel::ServerMessages, message);
+                        broadcast_lobby(&lobby, &mut server);
+                        continue;
+                    }
+                    None => "the match is full".to_string(),
+                }
+            }
+            Ok(ClientMessage::Hello { version }) => {
+                format!("protocol version {} is not {}", version, PROTOCOL_VERSION)
+            }
+            _ => "expected a hello".to_string(),
+        };
+        info!("Refusing player {}: {}", client_id, refusal);
+        let message = encode(&ServerMessage::Refused { reason: refusal });
+        server.send_message(client_id, ServerChannel::ServerMessages, message);
+        server.disconnect(client_id);
+    }
+}
+
+// before the match, seated clients pick their deck and toggle ready, the match starts once everyone is
+fn server_lobby(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    mut server: ResMut<RenetServer>,
+    registry: Res<CardRegistry>,
+    ball_kinds: Option<Res<BallKinds>>,
+    serve_rule: Option<Res<ServeRule>>,
+    server_match: Option<Res<ServerMatch>>,
+) {
+    if server_match.is_some() {
+        return;
+    }
+    let mut changed = false;
+    for client_id in server.clients_id() {
+        if !lobby.players.contains_key(&client_id) {
+            continue;
+        }
+        while let Some(message) = server.receive_message(client_id, ClientChannel::Command) {
+            match decode(&message) {
+                Ok(ClientMessage::Deck(deck)) => match deck.check(&registry) {
+                    Ok(()) => {
+                        lobby.decks.insert(client_id, deck.card_ids());
+                    }
+                    Err(error) => {
+                        info!("Player {} sent an unplayable deck: {}", client_id, error);
+                        let reason = error.to_string();
+                        let message = encode(&ServerMessage::DeckRejected { reason });
+                        server.send_message(client_id, ServerChannel::ServerMessages, message);
+                    }
+                },
+                Ok(ClientMessage::Ready(ready)) => {
+                    if let Some(entry) = lobby.players.get_mut(&client_id) {
+                        entry.ready = ready;
+                        changed = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if changed {
+        broadcast_lobby(&lobby, &mut server);
+        if lobby.is_full_and_ready() {
+            let ball_kinds = ball_kinds.map(|kinds| kinds.clone()).unwrap_or_default();
+            let serve_rule = serve_rule.map(|rule| *rule).unwrap_or_default();
+            start_match(
+                &mut commands,
+                &lobby,
+                &mut server,
+                &registry,
+                ball_kinds,
+                serve_rule,
+            );
+        }
+    }
+}
+
+fn start_match(
+    commands: &mut Commands,
+    lobby: &Lobby,
+    server: &mut RenetServer,
+    registry: &CardRegistry,
+    ball_kinds: BallKinds,
+    serve_rule: ServeRule,
+) {
+    let starter = Decklist::starter(registry).card_ids();
+    let mut decks = [starter.clone(), starter];
+    for (client_id, entry) in lobby.players.iter() {
+        if let Some(deck) = lobby.decks.get(client_id) {
+            decks[entry.seat.index()] = deck.clone();
+        }
+    }
+    let seed = random_seed();
+    info!("match seed {}", seed);
+    let [deck_one, deck_two] = &decks;
+    let mut match_state = MatchState::new(registry, [deck_one, deck_two], seed)
+        .expect("the decks were checked against the registry")
+        .with_ball_kinds(ball_kinds)
+        .with_serve_rule(serve_rule);
+    match_state.deal();
+    for (client_id, entry) in lobby.players.iter() {
+        match_state.player_mut(entry.seat).id = PlayerId::Client(client_id.raw());
+    }
+    for (client_id, entry) in lobby.players.iter() {
+        let message = encode(&ServerMessage::MatchStarted {
+            seat: entry.seat,
+            state: Box::new(match_state.view_for(entry.seat)),
+        });
+        server.send_message(*client_id, ServerChannel::ServerMessages, message);
+    }
+    info!("Match started.");
+    commands.insert_resource(ServerMatch(match_state));
+    commands.insert_resource(PendingInputs::default());
+}
+
+// the server is the only place the rules are applied, clients just send what they would like to do
+fn server_receive_actions(
+    lobby: Res<Lobby>,
+    mut server: ResMut<RenetServer>,
+    mut match_state: ResMut<ServerMatch>,
+) {
+    for client_id in server.clients_id() {
+        let Some(seat) = lobby.seat_of(client_id) else {
+            continue;
+        };
+        while let Some(message) = server.receive_message(client_id, ClientChannel::Command) {
+            let Ok(ClientMessage::Action(action)) = decode(&message) else {
+                info!("Player {} sent an invalid message.", client_id);
+                continue;
+            };
+            let reply = match match_state.apply(seat, action) {
+                Ok(events) => {
+                    send_events(&lobby, &mut server, &match_state, &events);
+                    continue;
+                }
+                Err(error) => ServerMessage::ActionRejected {
+                    action,
+                    reason: error.to_string(),
+                },
+            };
+            let message = encode(&reply);
+            server.send_message(client_id, ServerChannel::ServerMessages, message);
+        }
+    }
+}
+
+// the paddles move on the server. Clients predict theirs ahead of it, so each input waits for
+// the tick it was made for, a late one applies right away and the next snapshot sets the client straight
+fn server_receive_input(
+    lobby: Res<Lobby>,
+    mut server: ResMut<RenetServer>,
+    mut pending: ResMut<PendingInputs>,
+    mut match_state: ResMut<ServerMatch>,
+) {
+    for client_id in server.clients_id() {
+        let Some(seat) = lobby.seat_of(client_id) else {
+            continue;
+        };
+        while let Some(message) = server.receive_message(client_id, ClientChannel::Input) {
+            let Ok(inputs) = decode::<Vec<PlayerInput>>(&message) else {
+                info!("Player {} sent an invalid input.", client_id);
+                continue;
+            };
+            for input in inputs {
+                if input.tick > pending.newest[seat.index()] {
+                    pending.newest[seat.index()] = input.tick;
+                    pending.inputs[seat.index()].push(input);
+                }
+            }
+        }
+    }
+    let next = match_state.tick + 1;
+    for seat in Seat::ALL {
+        let inputs = &mut pending.inputs[seat.index()];
+        let due = inputs
+            .iter()
+            .rposition(|input| input.tick <= next || input.tick > next + PendingInputs::MAX_LEAD);
+        if let Some(last) = due {
+            let direction = inputs[last].direction();
+            inputs.drain(..=last);
+            match_state.steer(seat, direction);
+        }
+    }
+}
+
+fn server_tick_match(
+    lobby: Res<Lobby>,
+    mut server: ResMut<RenetServer>,
+    mut match_state: ResMut<ServerMatch>,
+) {
+    let events = match_state.tick(MatchState::TIMESTEP);
+    if !events.is_empty() {
+        send_events(&lobby, &mut server, &match_state, &events);
+    }
+}
+
+// nobody gets the whole match, each seat sees the cards it is allowed to
+fn send_events(
+    lobby: &Lobby,
+    server: &mut RenetServer,
+    match_state: &MatchState,
+    events: &[MatchEvent],
+) {
+    for (client_id, entry) in lobby.players.iter() {
+        let events = match_state.events_for(entry.seat, events);
+        let message = encode(&ServerMessage::Match(events));
+        server.send_message(*client_id, ServerChannel::ServerMessages, message);
+    }
+}
+
+fn server_sync_match(mut server: ResMut<RenetServer>, match_state: Res<ServerMatch>) {
+    let sync_message = encode(&Snapshot::of(&match_state));
+    server.broadcast_message(ServerChannel::NetworkedEntities, sync_message);
+}
+
+// once the match is decided the seats go back to the lobby, ready for another one
+fn server_end_match(
+    mut commands: Commands,
+    mut lobby: ResMut<Lobby>,
+    mut server: ResMut<RenetServer>,
+    match_state: Res<ServerMatch>,
+) {
+    let Some(result) = match_state.result() else {
+        return;
+    };
+    info!(
+        "Match ended: {:?} won, the other side {}",
+        result.winner, result.reason
+    );
+    commands.remove_resource::<ServerMatch>();
+    lobby.unready();
+    broadcast_lobby(&lobby, &mut server);
+}
@@ -1,118 +1,74 @@
-use bevy::prelude::*;
-use bevy_renet::{
-    client_connected,
-    renet::{
-        transport::{ClientAuthentication, ServerAuthentication, ServerConfig},
-        ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent,
-    },
-    transport::{NetcodeClientPlugin, NetcodeServerPlugin},
-    RenetClientPlugin, RenetServerPlugin,
-};
-use renet::{
-    transport::{NetcodeClientTransport, NetcodeServerTransport, NetcodeTransportError},
-    ClientId,
-};
-use std::time::SystemTime;
-use std::{collections::HashMap, net::UdpSocket};
-use serde::{Deserialize, Serialize};
+use bevy::{app::ScheduleRunnerPlugin, prelude::*};
+use bevy_renet::{transport::NetcodeServerPlugin, RenetServerPlugin};
+use poplar_core::{BallKinds, CardPool, CardRegistry, MatchState, PoolFormat, ServeRule};
+use renet_server::{new_renet_server, MatchServerPlugin};
+use std::{path::Path, process, time::Duration};
 
-const PROTOCOL_ID: u64 = 7;
+const CARD_POOL_PATH: &str = "../../assets/cards.csv";
+const BALL_KINDS_PATH: &str = "../../assets/balls.ron";
 
-#[derive(Debug, Default, Resource)]
-struct Lobby {
-    players: HashMap<ClientId, Entity>,
-}
-
-#[derive(Debug, Default, Serialize, Deserialize, Component, Resource)]
-struct PlayerInput {
-    up: bool,
-    down: bool,
-    left: bool,
-    right: bool,
+fn load_card_pool(path: &str) -> CardRegistry {
+    let format = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(PoolFormat::from_extension)
+        .expect("the card pool has to be a .csv, .ron or .json file");
+    let pool = CardPool::parse(format, &std::fs::read(path).unwrap());
+    // refuse to serve matches with broken cards
+    if !pool.is_valid() {
+        for error in pool.errors() {
+            eprintln!("{}: {}", path, error);
+        }
+        process::exit(1);
+    }
+    CardRegistry::from(&pool)
 }
 
-#[derive(Debug, Component)]
-struct Player {
-    id: ClientId,
+fn load_ball_kinds(path: &str) -> BallKinds {
+    match BallKinds::parse(&std::fs::read(path).unwrap()) {
+        Ok(kinds) => kinds,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Component)]
-enum ServerMessages {
-    PlayerConnected { id: ClientId },
-    PlayerDisconnected { id: ClientId },
+fn parse_serve_rule(name: &str) -> ServeRule {
+    ServeRule::from_name(name).unwrap_or_else(|| {
+        eprintln!("{} is no serve rule, try loser_serves or alternate", name);
+        process::exit(1);
+    })
 }
 
-fn new_renet_server() -> (RenetServer, NetcodeServerTransport) {
-    let public_addr = "127.0.0.1:5000".parse().unwrap();
-    let socket = UdpSocket::bind(public_addr).unwrap();
-    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
-    let server_config = ServerConfig {
-        current_time,
-        max_clients: 64,
-        protocol_id: PROTOCOL_ID,
-        public_addresses: vec![public_addr],
-        authentication: ServerAuthentication::Unsecure,
-    };
-
-    let transport = NetcodeServerTransport::new(server_config, socket).unwrap();
-    let server = RenetServer::new(ConnectionConfig::default());
+fn main() {
+    let card_pool_path = std::env::args()
+        .nth(1)
+        .unwrap_or(CARD_POOL_PATH.to_string());
+    let ball_kinds_path = std::env::args()
+        .nth(2)
+        .unwrap_or(BALL_KINDS_PATH.to_string());
+    let serve_rule = std::env::args()
+        .nth(3)
+        .map_or(ServeRule::default(), |name| parse_serve_rule(&name));
 
-    (server, transport)
-}
+    let mut app = App::new();
+    app.add_plugins(
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            1.0 / MatchState::TICK_RATE as f64,
+        ))),
+    );
 
-fn main() {
-    let mut app =App::new();
-    app.add_plugins(MinimalPlugins);
-        
     app.add_plugins(RenetServerPlugin);
     app.add_plugins(NetcodeServerPlugin);
-    let (server, transport) = new_renet_server();
+    app.add_plugins(bevy::log::LogPlugin::default());
+    let public_addr = "127.0.0.1:5000".parse().unwrap();
+    let (server, transport) = new_renet_server(public_addr, vec![public_addr]).unwrap();
     app.insert_resource(server);
     app.insert_resource(transport);
-    app.init_resource::<Lobby>();
-
-    app.add_systems(
-        Update,
-        (server_update_system, server_sync_players).run_if(resource_exists::<RenetServer>),
-    );
+    app.insert_resource(load_card_pool(&card_pool_path));
+    app.insert_resource(load_ball_kinds(&ball_kinds_path));
+    app.insert_resource(serve_rule);
+    app.add_plugins(MatchServerPlugin);
     app.run();
 }
-
-fn server_update_system(
-    mut server_events: EventReader<ServerEvent>,
-    mut commands: Commands,
-    mut lobby: ResMut<Lobby>,
-    mut server: ResMut<RenetServer>,
-) {
-    for event in server_events.read() {
-        match event {
-            ServerEvent::ClientConnected { client_id } => {
-                println!("Player {} connected.", client_id);
-                let message = bincode::serialize(&ServerMessages::PlayerConnected { id: *client_id }).unwrap();
-                server.broadcast_message(DefaultChannel::ReliableOrdered, message);
-            }
-            ServerEvent::ClientDisconnected { client_id, reason } => {
-                println!("Player {} disconnected: {}", client_id, reason);
-                let message = bincode::serialize(&ServerMessages::PlayerDisconnected { id: *client_id }).unwrap();
-                server.broadcast_message(DefaultChannel::ReliableOrdered, message);
-            }
-        }
-    }
-
-    for client_id in server.clients_id() {
-        while let Some(message) = server.receive_message(client_id, DefaultChannel::ReliableOrdered) {
-            let player_input: PlayerInput = bincode::deserialize(&message).unwrap();
-        }
-    }
-}
-
-fn server_sync_players(mut server: ResMut<RenetServer>, query: Query<(&Transform, &Player)>) {
-    let mut players: HashMap<ClientId, [f32; 3]> = HashMap::new();
-    for (transform, player) in query.iter() {
-        players.insert(player.id, transform.translation.into());
-    }
-
-    let sync_message = bincode::serialize(&players).unwrap();
-    server.broadcast_message(DefaultChannel::Unreliable, sync_message);
-}
-
@@ -1,58 +1,131 @@
-use std::collections::VecDeque;
 use bevy::prelude::*;
+use bevy_renet::renet::RenetClient;
+use poplar_core::{random_seed, Action, BallKinds, Decklist, MatchEvent, MatchState, Seat};
 
+use crate::AppState;
 
-#[derive(Clone, Copy)]
-pub enum PlayerAction {
-    // move card from [0] -> [1]
-    MoveCard(Vec3, Vec3),
+pub mod record;
+use record::{record_match, start_record, MatchRecord};
 
-    // place a card in chess
-    PlaceCard(Vec3)
-}
-
-pub enum PlayerUni {
-    // self
-    Player1,
-    //enemy
-    Player2
-}
+use super::{
+    card::CardRegistry,
+    deck::decklists::Decklists,
+    systemsets::PlayingSets,
+    net::{
+        client::{is_online, ConnectionStatus},
+        protocol::{encode, ClientChannel, ClientMessage},
+    },
+    LocalData,
+};
 
+/// A player asks the rules to do something. Nothing changes until the rules accept it.
 #[derive(Event)]
 pub struct PlayerActionEvt {
-    player_uni: PlayerUni,
-    action: PlayerAction,
+    pub seat: Seat,
+    pub action: Action,
 }
 
+/// Something the rules changed in the match, the visuals follow these.
+#[derive(Event, Deref)]
+pub struct EvtMatch(pub MatchEvent);
+
 pub struct ActionPlugin;
 
 impl Plugin for ActionPlugin {
     fn build(&self, app: &mut App) {
         app
             .add_event::<PlayerActionEvt>()
+            .add_event::<EvtMatch>()
+            .init_resource::<MatchRecord>()
+            .add_systems(OnEnter(AppState::Playing), (setup_match, start_record))
+            .add_systems(OnExit(AppState::Playing), teardown_match)
             .add_systems(Update, (
+                concede,
                 listen_actions,
-            ));
+                record_match,
+            ).chain().run_if(resource_exists::<MatchState>))
+            // online the server plays the ticks, the prediction only moves the match along
+            .add_systems(FixedUpdate, tick_match
+                .in_set(PlayingSets::Main)
+                .run_if(not(is_online))
+                .run_if(resource_exists::<MatchState>));
+    }
+}
+
+fn setup_match(
+    mut commands: Commands,
+    registry: Res<CardRegistry>,
+    ball_kinds: Res<BallKinds>,
+    decklists: Res<Decklists>,
+    online_match: Option<Res<MatchState>>,
+    local_data: Res<LocalData>,
+    mut events: EventWriter<EvtMatch>,
+) {
+    // an online match arrives already dealt from the server, only its hand needs showing
+    if let Some(match_state) = online_match {
+        let seat = local_data.seat;
+        events.send_batch(match_state.player(seat).hand.iter().map(|card| {
+            EvtMatch(MatchEvent::CardDrawn { seat, card: card.id })
+        }));
+        return;
+    }
+    // our picked deck against the starter deck
+    let mut decks = [Decklist::starter(&registry).card_ids(), Decklist::starter(&registry).card_ids()];
+    decks[local_data.seat.index()] = decklists.deck_for_match(&registry).card_ids();
+    let seed = random_seed();
+    info!("match seed {}", seed);
+    let [deck_one, deck_two] = &decks;
+    let mut match_state = MatchState::new(&registry, [deck_one, deck_two], seed)
+        .expect("the decks were checked against the registry")
+        .with_ball_kinds(ball_kinds.clone());
+    events.send_batch(match_state.deal().into_iter().map(EvtMatch));
+    commands.insert_resource(match_state);
+}
+
+fn teardown_match(mut commands: Commands) {
+    commands.remove_resource::<MatchState>();
+}
+
+// the rules run the balls, paddles and timers a whole tick at a time like on the server
+fn tick_match(mut match_state: ResMut<MatchState>, mut events: EventWriter<EvtMatch>) {
+    events.send_batch(match_state.tick(MatchState::TIMESTEP).into_iter().map(EvtMatch));
+}
+
+// escape gives the match up
+fn concede(
+    input: Res<ButtonInput<KeyCode>>,
+    local_data: Res<LocalData>,
+    mut actions: EventWriter<PlayerActionEvt>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        actions.send(PlayerActionEvt {
+            seat: local_data.seat,
+            action: Action::Concede,
+        });
     }
 }
 
-fn listen_actions(
+pub fn listen_actions(
+    mut match_state: ResMut<MatchState>,
     mut player_event: EventReader<PlayerActionEvt>,
+    mut events: EventWriter<EvtMatch>,
+    status: Res<ConnectionStatus>,
+    mut client: Option<ResMut<RenetClient>>,
 ) {
     for evt in player_event.read() {
-        match evt.player_uni {
-            PlayerUni::Player1 => {
+        // online the server has the final word, what it accepts comes back as match events
+        if let (true, Some(client)) = (status.is_connected(), client.as_mut()) {
+            let message = encode(&ClientMessage::Action(evt.action));
+            client.send_message(ClientChannel::Command, message);
+            continue;
+        }
+        match match_state.apply(evt.seat, evt.action) {
+            Ok(result) => {
+                events.send_batch(result.into_iter().map(EvtMatch));
             }
-            PlayerUni::Player2 => {
-                match evt.action {
-                    PlayerAction::MoveCard(st, ed) => {
-                        
-                    }
-                    PlayerAction::PlaceCard(pos) => {
-                        
-                    }
-                }
+            Err(error) => {
+                info!("{:?} can't {:?}: {}", evt.seat, evt.action, error);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,69 @@
+//! Keeps track of how the match went, the game over screen shows it once it is decided.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use poplar_core::{MatchEvent, MatchResult, Seat};
+
+use crate::AppState;
+
+use super::EvtMatch;
+
+/// The last match played, reset whenever a new one starts.
+#[derive(Resource, Default, Debug)]
+pub struct MatchRecord {
+    pub result: Option<MatchResult>,
+    pub duration: Duration,
+    pub seats: [SeatRecord; 2],
+}
+
+impl MatchRecord {
+    pub fn seat(&self, seat: Seat) -> &SeatRecord {
+        &self.seats[seat.index()]
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SeatRecord {
+    /// creatures, spells, equipment and field cards alike
+    pub cards_played: u32,
+    pub creatures_lost: u32,
+    pub damage_taken: i32,
+}
+
+pub(super) fn start_record(mut record: ResMut<MatchRecord>) {
+    *record = MatchRecord::default();
+}
+
+// the match ends on the event telling so, whether the rules ran here or on the server
+pub(super) fn record_match(
+    time: Res<Time>,
+    mut events: EventReader<EvtMatch>,
+    mut record: ResMut<MatchRecord>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if record.result.is_none() {
+        record.duration += time.delta();
+    }
+    for evt in events.read() {
+        match **evt {
+            MatchEvent::CardPlayed { seat, .. }
+            | MatchEvent::SpellCast { seat, .. }
+            | MatchEvent::CardAttached { seat, .. }
+            | MatchEvent::FieldPlayed { seat, .. } => {
+                record.seats[seat.index()].cards_played += 1;
+            }
+            MatchEvent::CardDestroyed { seat, .. } => {
+                record.seats[seat.index()].creatures_lost += 1;
+            }
+            MatchEvent::PlayerHurt { seat, damage, .. } => {
+                record.seats[seat.index()].damage_taken += damage;
+            }
+            MatchEvent::MatchEnded { winner, reason } => {
+                record.result = Some(MatchResult { winner, reason });
+                next_state.set(AppState::GameOverMenu);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    utils::BoxedFuture,
+};
+use poplar_core::{BallKindError, BallKinds};
+use thiserror::Error;
+
+/// The ball kinds the game plays with.
+pub const BALL_KINDS_PATH: &str = "balls.ron";
+
+/// Reads and checks `balls.ron`.
+#[derive(Default)]
+pub struct BallKindsLoader;
+
+#[derive(Error, Debug)]
+pub enum BallKindsLoadError {
+    #[error("could not read the ball kinds: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Invalid(#[from] BallKindError),
+}
+
+impl AssetLoader for BallKindsLoader {
+    type Asset = BallKinds;
+    type Settings = ();
+    type Error = BallKindsLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<BallKinds, BallKindsLoadError>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(BallKinds::parse(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["balls.ron"]
+    }
+}
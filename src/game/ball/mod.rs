@@ -1,60 +1,95 @@
-use bevy::prelude::*;
+pub mod kinds;
+
+use bevy::{prelude::*, utils::HashMap};
 use bevy_rapier3d::geometry::Collider;
 use bevy_rapier3d::prelude::*;
-use bevy_rapier3d::rapier::dynamics::RigidBodyBuilder;
+use kinds::BallKindsLoader;
+use poplar_core::{BallId, BallKind, BallKindId, BallKinds, MatchState};
+
+use crate::AppState;
 
-use super::systemsets::PlayingSets;
+use super::{actions::listen_actions, net::prediction::Prediction, systemsets::PlayingSets};
 pub struct BallPlugin;
 
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<BallData>()
-            .add_systems(Startup, spawn_ball.in_set(PlayingSets::Main))
-            .add_systems(Update, on_spawn_ball.in_set(PlayingSets::Main))
-            .add_systems(Update, rotate_to_velocity.in_set(PlayingSets::Main));
+        app.init_asset::<BallKinds>()
+            .init_asset_loader::<BallKindsLoader>()
+            .init_resource::<BallData>()
+            .add_systems(OnEnter(AppState::Playing), spawn_countdown)
+            .add_systems(OnExit(AppState::Playing), despawn_balls)
+            .add_systems(
+                Update,
+                (
+                    (spawn_balls, place_balls, rotate_to_velocity).chain().after(listen_actions),
+                    update_countdown.run_if(resource_changed::<MatchState>),
+                )
+                    .in_set(PlayingSets::Main)
+                    .run_if(resource_exists::<MatchState>),
+            );
     }
 }
 
+/// One of the balls in play, the rules know it by `id`.
 #[derive(Component)]
 pub struct Ball {
-    power: u32,
+    pub id: BallId,
 }
 
 impl Ball {
-    pub const BALL_DEFAULT_SPEED: f32 = 1.0;
-    pub const BALL_DEFAULT_RADIUS: f32 = 0.1;
-    pub fn try_deal_damage() {
-        //TODO: deal damage
-        todo!();
-    }
+    /// where the balls roll, their height depends on their radius
+    pub const TABLE_TOP: f32 = 0.158;
 }
 
-pub fn rotate_to_velocity(mut balls: Query<&mut Velocity, With<Ball>>) {
-    for mut velocity in balls.iter_mut() {
-        velocity.angvel = Vec3::new(velocity.linvel.y, velocity.linvel.x, 0.0)
-    }
-}
+/// The seconds left before a served ball starts moving, hidden while every ball is in play.
+#[derive(Component)]
+struct ServeCountdown;
 
-pub fn spawn_ball(mut commands: Commands) {
-    commands.spawn(BallBundle {
-        ball: Ball { power: 1 },
-        transform: Transform::from_xyz(0.0, 0.0, 0.258),
-        global_transform: default(),
-        collider: Collider::ball(Ball::BALL_DEFAULT_RADIUS),
-        visibility: default(),
-        computed_visibiltiy: default(),
-        rigid_body: RigidBody::Dynamic,
-    });
+// the balls roll the way the rules send them
+pub fn rotate_to_velocity(
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    mut balls: Query<(&Ball, &mut Transform)>,
+) {
+    for (ball, mut transform) in balls.iter_mut() {
+        if let Some(state) = match_state.ball(ball.id) {
+            let spin = Vec3::new(state.velocity.y, state.velocity.x, 0.0) * time.delta_seconds();
+            transform.rotate(Quat::from_scaled_axis(spin));
+        }
+    }
 }
 
-pub fn on_spawn_ball(
+// a ball entity for every ball the rules have in play, whether they were served here or on the server.
+// The rules move them, their kinematic bodies only follow
+fn spawn_balls(
     mut commands: Commands,
-    balls: Query<(Entity, &Ball), Added<Ball>>,
-    ball_data: Res<BallData>,
+    mut ball_data: ResMut<BallData>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    match_state: Res<MatchState>,
+    balls: Query<(Entity, &Ball)>,
 ) {
-    for (ball_entity, ball) in balls.iter() {
+    for (entity, ball) in balls.iter() {
+        if match_state.ball(ball.id).is_none() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    for state in match_state.balls.iter() {
+        if balls.iter().any(|(_, ball)| ball.id == state.id) {
+            continue;
+        }
+        let kind = match_state.ball_kinds.get(&state.kind);
+        let (mesh, material) = ball_data.visuals(kind, &mut meshes, &mut materials);
         commands
-            .entity(ball_entity)
+            .spawn(BallBundle {
+                ball: Ball { id: state.id },
+                transform: Transform::from_translation(state.position.extend(Ball::TABLE_TOP + kind.radius)),
+                global_transform: default(),
+                collider: Collider::ball(kind.radius),
+                visibility: default(),
+                computed_visibiltiy: default(),
+                rigid_body: RigidBody::KinematicPositionBased,
+            })
             .with_children(|parent| {
                 parent
                     .spawn(SpatialBundle {
@@ -62,54 +97,108 @@ pub fn on_spawn_ball(
                     })
                     .with_children(|parent| {
                         parent.spawn(PbrBundle {
-                            material: ball_data.ball_base_material.clone(),
-                            mesh: ball_data.mesh.clone(),
+                            material,
+                            mesh,
                             ..default()
                         });
                     });
-            })
-            .insert(Damping {
-                linear_damping: 0.0,
-                angular_damping: 0.0,
-            })
-            .insert(Friction {
-                coefficient: 0.0,
-                combine_rule: CoefficientCombineRule::Min,
-            })
-            .insert(Restitution {
-                coefficient: 1.0,
-                combine_rule: CoefficientCombineRule::Max,
-            })
-            .insert(LockedAxes::TRANSLATION_LOCKED_Z)
-            .insert(AdditionalMassProperties::Mass(1.0))
-            // .insert(Ccd::enabled())
-            .insert(Velocity::linear(Vec3::new(0.0, 1.0, 0.0)));
+            });
     }
 }
 
-#[derive(Resource)]
-pub struct BallData {
-    mesh: Handle<Mesh>,
-    ball_base_material: Handle<StandardMaterial>,
+// the balls are drawn where the rules have them, online eased into the corrections of the prediction
+fn place_balls(
+    match_state: Res<MatchState>,
+    prediction: Res<Prediction>,
+    mut balls: Query<(&Ball, &mut Transform)>,
+) {
+    for (ball, mut transform) in balls.iter_mut() {
+        if let Some(state) = match_state.ball(ball.id) {
+            let position = state.position + prediction.ball_offset(ball.id);
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
 }
 
-impl FromWorld for BallData {
-    fn from_world(world: &mut World) -> Self {
-        let world = world.cell();
-        let mut meshes = world.resource_mut::<Assets<Mesh>>();
-        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
-        let card_base_material = StandardMaterial {
-            alpha_mode: AlphaMode::Blend,
-            base_color: Color::BLUE,
+fn spawn_countdown(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 80.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(48.0),
+            top: Val::Percent(40.0),
             ..default()
-        };
-        Self {
-            mesh: meshes.add(Sphere::new(Ball::BALL_DEFAULT_RADIUS)),
-            ball_base_material: materials.add(card_base_material),
+        }),
+        ServeCountdown,
+    ));
+}
+
+// the ball served first goes first
+fn update_countdown(
+    match_state: Res<MatchState>,
+    mut texts: Query<(&mut Text, &mut Visibility), With<ServeCountdown>>,
+) {
+    let countdown = match_state
+        .balls
+        .iter()
+        .filter(|ball| ball.is_waiting())
+        .map(|ball| ball.countdown)
+        .reduce(f32::min);
+    for (mut text, mut visibility) in texts.iter_mut() {
+        match countdown {
+            Some(countdown) => {
+                text.sections[0].value = format!("{}", countdown.ceil() as u32);
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
         }
     }
 }
 
+pub fn despawn_balls(
+    mut commands: Commands,
+    balls: Query<Entity, Or<(With<Ball>, With<ServeCountdown>)>>,
+) {
+    for entity in balls.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// The mesh and material of every ball kind, made the first time a ball of it is served.
+#[derive(Resource, Default)]
+pub struct BallData {
+    visuals: HashMap<BallKindId, (Handle<Mesh>, Handle<StandardMaterial>)>,
+}
+
+impl BallData {
+    fn visuals(
+        &mut self,
+        kind: &BallKind,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> (Handle<Mesh>, Handle<StandardMaterial>) {
+        self.visuals
+            .entry(kind.id.clone())
+            .or_insert_with(|| {
+                let (r, g, b) = kind.color;
+                let material = StandardMaterial {
+                    alpha_mode: AlphaMode::Blend,
+                    base_color: Color::rgb_u8(r, g, b),
+                    ..default()
+                };
+                (meshes.add(Sphere::new(kind.radius)), materials.add(material))
+            })
+            .clone()
+    }
+}
+
 #[derive(Bundle)]
 pub struct BallBundle {
     pub ball: Ball,
@@ -71,16 +71,16 @@ pub fn move_camera(
 
     for (camera, mut transform) in &mut cameras {
         let mut direction = Vec3::ZERO;
-        if input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) {
+        if input.pressed(KeyCode::ArrowLeft) {
             direction.x -= 1.0;
         }
-        if input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) {
+        if input.pressed(KeyCode::ArrowRight) {
             direction.x += 1.0;
         }
-        if input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) {
+        if input.pressed(KeyCode::ArrowUp) {
             direction.y += 1.0;
         }
-        if input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) {
+        if input.pressed(KeyCode::ArrowDown) {
             direction.y -= 1.0;
         }
 
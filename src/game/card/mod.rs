@@ -3,77 +3,118 @@ use std::f32::consts::PI;
 use bevy::{pbr::NotShadowCaster, prelude::*, transform, window::PrimaryWindow};
 use meshtext::{error::MeshTextError, MeshGenerator, MeshText, TextSection};
 mod animations;
+pub mod moves;
+pub mod pool;
 
 use animations::CardAnimations;
+use moves::{
+    card_moves_from_rules, log_card_moves, move_cards_between_zones, EvtCardMoved, EvtMoveCard,
+    MoveCause,
+};
+use pool::CardPoolLoader;
 use bevy_rapier3d::{geometry::Collider, pipeline::QueryFilter, plugin::RapierContext};
-use serde::{Deserialize, Serialize};
+use poplar_core::{Action, CardInstance, CardInstanceId, MatchEvent, MatchState, PlayerId};
 use std::mem;
 
 use crate::{game::slot, AppState};
 
+pub use poplar_core::card::{CardInfo, CardStats, CardType, ISizeWithMax};
+pub use poplar_core::{CardPool, CardRegistry};
+
 use super::{
+    actions::{listen_actions, EvtMatch, PlayerActionEvt},
     camera::PlayerCamera,
     hand::Hand,
-    player::Player,
+    player::{EvtEnergyRefused, Player},
     slot::{HoveredSlot, Slot, SlotType},
     systemsets::PlayingSets,
     LocalData,
 };
 pub struct CardPlugin;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ISizeWithMax {
-    pub current: isize,
-    pub max: isize,
-}
-
 impl Plugin for CardPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SelectedCard>()
+        app.init_asset::<CardPool>()
+            .init_asset_loader::<CardPoolLoader>()
+            .init_resource::<SelectedCard>()
             .init_resource::<HoverPoint>()
             .init_resource::<CardData>()
             .add_event::<EvtSpawnCard>()
-            .add_systems(OnEnter(AppState::Playing), spawn_cards)
+            .add_event::<EvtMoveCard>()
+            .add_event::<EvtCardMoved>()
+            .add_systems(OnExit(AppState::Playing), despawn_cards)
             .add_systems(PostUpdate, on_spawn_card.in_set(PlayingSets::Main))
             .add_systems(
                 Update,
                 (select_card, move_cards).chain().in_set(PlayingSets::Main),
             )
-            .add_systems(Update, evt_spawn_card);
+            .add_systems(
+                Update,
+                (card_moves_from_rules, on_card_stats_changed)
+                    .after(listen_actions)
+                    .in_set(PlayingSets::Main),
+            )
+            .add_systems(Update, update_cooldown_overlay.in_set(PlayingSets::Main))
+            .add_systems(Update, evt_spawn_card.run_if(resource_exists::<MatchState>))
+            .add_systems(
+                Update,
+                (move_cards_between_zones, log_card_moves)
+                    .chain()
+                    .after(select_card)
+                    .after(card_moves_from_rules)
+                    .after(evt_spawn_card),
+            );
     }
 }
 
+// the next match starts with an empty table
+fn despawn_cards(
+    mut commands: Commands,
+    mut selected_card: ResMut<SelectedCard>,
+    cards: Query<Entity, With<Card>>,
+) {
+    for entity in cards.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *selected_card = SelectedCard::default();
+}
+
+/// Shows a card coming out of a zone off the table, it then moves to `slot_type`.
 #[derive(Event)]
 pub struct EvtSpawnCard {
+    pub from: SlotType,
     pub slot_type: SlotType,
-    pub card_info: CardInfo,
+    pub card: CardInstance,
 }
 
 fn evt_spawn_card(
     mut commands: Commands,
     mut events: EventReader<EvtSpawnCard>,
-    mut hand: Query<&mut Hand>,
-    mut slots: Query<&mut Slot>,
+    mut moves: EventWriter<EvtMoveCard>,
+    match_state: Res<MatchState>,
 ) {
     for evt in events.read() {
-        let card_info = evt.card_info.clone();
-        let entity = commands.spawn(CardBundle {
-            transform: Transform::from_xyz(0.5, 0.0, 0.1),
-            global_transform: default(),
-            card: Card::from(card_info.clone()),
-            collider: Collider::cuboid(Card::ASPECT_RATIO / 2.0, 1.0 / 2.0, 0.2),
-            visibility: default(),
-            computed_visibiltiy: default(),
-            slot_type: evt.slot_type,
-        });
-    }
-}
-
-fn spawn_cards(card_infos: Res<Assets<CardInfo>>, mut event: EventWriter<EvtSpawnCard>) {
-    for (_, card_info) in card_infos.iter() {
-        event.send(EvtSpawnCard {
-            slot_type: SlotType::Anywhere,
-            card_info: card_info.clone(),
+        let player_id = match_state.player(evt.card.owner).id;
+        let entity = commands
+            .spawn(CardBundle {
+                transform: Transform::from_xyz(0.5, 0.0, 0.1),
+                global_transform: default(),
+                card: Card::new(evt.card.clone(), player_id),
+                collider: Collider::cuboid(Card::ASPECT_RATIO / 2.0, 1.0 / 2.0, 0.2),
+                visibility: default(),
+                computed_visibiltiy: default(),
+                slot_type: evt.from,
+            })
+            .id();
+        moves.send(EvtMoveCard {
+            card: entity,
+            from_zone: evt.from,
+            to_zone: evt.slot_type,
+            to_slot: None,
+            cause: match evt.from {
+                SlotType::Deck => MoveCause::Drawn,
+                _ => MoveCause::Returned,
+            },
         });
     }
 }
@@ -86,14 +127,21 @@ pub struct CardBundle {
     pub collider: Collider,
     pub visibility: Visibility,
     pub computed_visibiltiy: InheritedVisibility,
+    /// the zone the card is in, kept up to date by [`EvtMoveCard`]
     pub slot_type: SlotType,
 }
 
 #[derive(Component)]
 pub struct Card {
     pub animations: CardAnimations,
+    pub instance: CardInstanceId,
     pub info: CardInfo,
-    pub player_id: String,
+    /// copied from the `MatchState` whenever buffs or damage change them
+    pub stats: CardStats,
+    pub health: ISizeWithMax,
+    /// seconds before the card may act again, counted down by the slot it is in
+    pub cooldown: f32,
+    pub player_id: PlayerId,
     pub slotted_in_slot: Option<Entity>,
 }
 
@@ -109,15 +157,59 @@ impl Card {
         self.info.card_type
     }
 
-    pub fn is_player_controlled(&self) -> bool {
-        return if self.player_id == "todo".to_owned() {
-            true
-        } else {
-            false
+    pub fn new(card: CardInstance, player_id: PlayerId) -> Self {
+        Self {
+            instance: card.id,
+            stats: card.stats(),
+            health: card.health(),
+            cooldown: card.cooldown,
+            info: card.info,
+            player_id,
+            animations: default(),
+            slotted_in_slot: default(),
+        }
+    }
+
+    pub fn is_player_controlled(&self, local_data: &LocalData) -> bool {
+        self.player_id == local_data.player_id()
+    }
+}
+
+/// A number on the card face, redrawn when the card stats change.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum StatLabel {
+    Cost,
+    Attack,
+    Health,
+}
+
+impl StatLabel {
+    fn text(&self, card: &Card) -> String {
+        match self {
+            StatLabel::Cost => card.stats.cost.to_string(),
+            StatLabel::Attack => card.stats.attack.to_string(),
+            StatLabel::Health => card.health.current.to_string(),
+        }
+    }
+
+    fn transform(&self) -> Transform {
+        let (x, y) = match self {
+            StatLabel::Cost => (-0.5, 0.35),
+            StatLabel::Attack => (-0.5, -0.55),
+            StatLabel::Health => (0.32, -0.55),
         };
+        Transform::from_xyz(x, y, 0.03).with_scale(Vec3::new(2.0, 2.0, 1.0))
     }
 }
 
+/// Darkens the card while it cools down, shrinking as the cooldown runs out.
+#[derive(Component)]
+struct CooldownOverlay;
+
+/// The whole seconds of cooldown left, on top of the overlay.
+#[derive(Component, Default)]
+struct CooldownLabel(u32);
+
 #[derive(Default, PartialEq, Eq, Copy, Clone, Resource)]
 pub enum SelectedCard {
     Some(Entity),
@@ -239,11 +331,15 @@ pub fn select_card(
     mut selected_card: ResMut<SelectedCard>,
     mut hover_point: ResMut<HoverPoint>,
     hovered_slot: Res<HoveredSlot>,
-    mut commands: Commands,
+    local_data: Res<LocalData>,
+    mut actions: EventWriter<PlayerActionEvt>,
+    mut moves: EventWriter<EvtMoveCard>,
+    mut refused: EventWriter<EvtEnergyRefused>,
+    match_state: Option<Res<MatchState>>,
     cameras: Query<(&Camera, &Transform), With<PlayerCamera>>,
-    mut cards: Query<(&mut Card, &Transform)>,
-    mut slots: Query<&mut Slot>,
-    mut transforms: Query<&mut Transform, (Without<Card>, Without<Camera>)>,
+    cards: Query<(&Card, &Transform, &SlotType)>,
+    slots: Query<&Slot>,
+    slot_owners: Query<&Player, With<Slot>>,
 ) {
     let window = windows.single();
     if let Some(cursor) = window.cursor_position() {
@@ -266,7 +362,7 @@ pub fn select_card(
         let far = ndc_to_world.project_point3(cursor_ndc.extend(far_ndc));
         let direction = far - near;
         if let SelectedCard::Some(card_entity) = selected_card.as_ref() {
-            let (_card, transfrom) = cards.get(card_entity.clone()).unwrap();
+            let (_card, transfrom, _) = cards.get(card_entity.clone()).unwrap();
 
             let denom = Vec3::Z.dot(direction);
             if denom.abs() > 0.0001 {
@@ -284,8 +380,8 @@ pub fn select_card(
         if mouse.just_pressed(MouseButton::Left) {
             let result = context.cast_ray(near, direction, 50.0, true, QueryFilter::new());
             if let Some((entity, _toi)) = result {
-                if let Ok((mut card, _transfrom)) = cards.get_mut(entity) {
-                    if card.is_player_controlled() {
+                if let Ok((card, _transfrom, _)) = cards.get(entity) {
+                    if card.is_player_controlled(&local_data) {
                         // unslot from tile
                         *selected_card = SelectedCard::Some(entity);
                     }
@@ -296,17 +392,57 @@ pub fn select_card(
 
     if mouse.just_released(MouseButton::Left) {
         if let SelectedCard::Some(card_entity) = *selected_card {
-            let (mut card, mut _transform) = cards.get_mut(card_entity).unwrap();
+            let (card, _transform, zone) = cards.get(card_entity).unwrap();
             *selected_card = SelectedCard::None;
-            if let Some(slot_entity) = hovered_slot.0 {
-                if let Ok(mut slot) = slots.get_mut(slot_entity) {
-                    if slot.try_slotting_card(card_entity) {
-                        if let Some(slot_entity) = card.slotted_in_slot {
-                            if let Ok(mut slot) = slots.get_mut(slot_entity) {
-                                slot.remove_slotted_entity();
+            // cards can't be dropped on the other side's slots
+            let hovered_slot = hovered_slot.0.filter(|slot_entity| {
+                slot_owners
+                    .get(*slot_entity)
+                    .map_or(true, |owner| owner.id() == card.player_id)
+            });
+            if let Some(slot_entity) = hovered_slot {
+                if let Ok(slot) = slots.get(slot_entity) {
+                    match (*zone, slot.get_type()) {
+                        // the rules decide, the card is moved once they accept it
+                        (SlotType::Hand, SlotType::Battlefield | SlotType::Field)
+                            if slot.accepts(card.card_type()) =>
+                        {
+                            // not worth asking the rules, the card just snaps back
+                            let energy = match_state
+                                .as_ref()
+                                .map_or(0, |state| state.player(local_data.seat).energy);
+                            if card.stats.cost.max(0) as u32 > energy {
+                                refused.send(EvtEnergyRefused);
+                                return;
                             }
+                            actions.send(PlayerActionEvt {
+                                seat: local_data.seat,
+                                action: Action::PlayCard {
+                                    card: card.instance,
+                                    slot: slot.index(),
+                                },
+                            });
+                        }
+                        (SlotType::Battlefield, SlotType::Battlefield) => {
+                            actions.send(PlayerActionEvt {
+                                seat: local_data.seat,
+                                action: Action::MoveCard {
+                                    card: card.instance,
+                                    slot: slot.index(),
+                                },
+                            });
+                        }
+                        // rearranging the hand is only cosmetic
+                        (SlotType::Hand, SlotType::Hand) => {
+                            moves.send(EvtMoveCard {
+                                card: card_entity,
+                                from_zone: SlotType::Hand,
+                                to_zone: SlotType::Hand,
+                                to_slot: Some(slot_entity),
+                                cause: MoveCause::Rearranged,
+                            });
                         }
-                        card.slotted_in_slot = Some(slot_entity);
+                        _ => {}
                     }
                 }
             }
@@ -314,26 +450,83 @@ pub fn select_card(
     }
 }
 
-#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub enum CardType {
-    Creature,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-pub struct CardStats {
-    pub cost: isize,
-    pub cooldown: isize,
+// buffs and damage change the numbers on the card face
+fn on_card_stats_changed(
+    mut events: EventReader<EvtMatch>,
+    match_state: Option<Res<MatchState>>,
+    mut cards: Query<&mut Card>,
+    mut labels: Query<(&StatLabel, &Parent, &mut Handle<Mesh>)>,
+    parents: Query<&Parent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Some(match_state) = match_state else {
+        return;
+    };
+    for evt in events.read() {
+        let (MatchEvent::CardBuffed { card: instance, .. }
+        | MatchEvent::CardDamaged { card: instance, .. }
+        | MatchEvent::CardAttached { host: instance, .. }) = **evt
+        else {
+            continue;
+        };
+        let Some(state) = match_state.card(instance) else {
+            continue;
+        };
+        for mut card in cards.iter_mut().filter(|card| card.instance == instance) {
+            card.stats = state.stats();
+            card.health = state.health();
+        }
+        for (label, face, mut mesh) in labels.iter_mut() {
+            let Ok(card) = parents
+                .get(face.get())
+                .and_then(|card_entity| cards.get(card_entity.get()))
+            else {
+                continue;
+            };
+            if card.instance == instance {
+                *mesh = meshes.add(generate_text_mesh(&label.text(card)));
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, TypePath, Asset, Clone)]
-pub struct CardInfo {
-    pub name: String,
-    pub name_zh: String,
-    pub desc: String,
-    pub card_type: CardType,
-    #[serde(flatten)]
-    pub stats: CardStats,
+fn update_cooldown_overlay(
+    cards: Query<&Card>,
+    parents: Query<&Parent>,
+    mut overlays: Query<(&Parent, &mut Transform, &mut Visibility), With<CooldownOverlay>>,
+    mut labels: Query<
+        (&Parent, &mut CooldownLabel, &mut Handle<Mesh>, &mut Visibility),
+        Without<CooldownOverlay>,
+    >,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let card_of = |face: &Parent| {
+        parents
+            .get(face.get())
+            .ok()
+            .and_then(|card_entity| cards.get(card_entity.get()).ok())
+    };
+    for (face, mut transform, mut visibility) in overlays.iter_mut() {
+        let Some(card) = card_of(face) else {
+            continue;
+        };
+        let left = (card.cooldown / card.stats.cooldown.max(1) as f32).clamp(0.0, 1.0);
+        // drains from the top down
+        transform.scale.y = left;
+        transform.translation.y = (left - 1.0) / 2.0;
+        *visibility = if left > 0.0 { Visibility::Inherited } else { Visibility::Hidden };
+    }
+    for (face, mut label, mut mesh, mut visibility) in labels.iter_mut() {
+        let Some(card) = card_of(face) else {
+            continue;
+        };
+        let seconds = card.cooldown.ceil() as u32;
+        if seconds != label.0 {
+            label.0 = seconds;
+            *mesh = meshes.add(generate_text_mesh(&seconds.to_string()));
+        }
+        *visibility = if seconds > 0 { Visibility::Inherited } else { Visibility::Hidden };
+    }
 }
 
 #[derive(Resource)]
@@ -342,6 +535,8 @@ pub struct CardData {
     portrait_mesh: Handle<Mesh>,
     card_base_material: Handle<StandardMaterial>,
     card_font_material: Handle<StandardMaterial>,
+    cooldown_material: Handle<StandardMaterial>,
+    cooldown_font_material: Handle<StandardMaterial>,
 }
 
 impl FromWorld for CardData {
@@ -367,6 +562,17 @@ impl FromWorld for CardData {
             portrait_mesh: meshes.add(Rectangle::new(Card::ART_ASPECT * 0.65, 0.65)),
             card_base_material: materials.add(card_base_material),
             card_font_material: materials.add(card_font_material),
+            cooldown_material: materials.add(StandardMaterial {
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                base_color: Color::rgba(0.0, 0.0, 0.0, 0.6),
+                ..default()
+            }),
+            cooldown_font_material: materials.add(StandardMaterial {
+                unlit: true,
+                base_color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            }),
         }
     }
 }
@@ -399,16 +605,19 @@ fn on_spawn_card(
     mut commands: Commands,
     card_data: Res<CardData>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut cards: Query<(Entity, &mut Card, &SlotType), Added<Card>>,
+    cards: Query<(Entity, &Card), Added<Card>>,
     mut meshes: ResMut<Assets<Mesh>>,
     asset_server: Res<AssetServer>,
-    mut slots: Query<&mut Slot>,
-    mut hands: Query<(&mut Hand, &Player)>,
+    hands: Query<&Player, With<Hand>>,
     local_data: Res<LocalData>,
 ) {
-    for (mut hand, player) in hands.iter_mut() {
-        if player.id() == local_data.player_id {
-            for (entity, mut card, slot_type) in cards.iter_mut() {
+    for player in hands.iter() {
+        if player.id() == local_data.player_id() {
+            for (entity, card) in cards.iter() {
+                // only our own cards are shown face up
+                if card.player_id != player.id() {
+                    continue;
+                }
                 commands.entity(entity).with_children(|parent| {
                     parent
                         .spawn(SpatialBundle {
@@ -426,7 +635,7 @@ fn on_spawn_card(
                                     mesh: card_data.portrait_mesh.clone(),
                                     material: materials.add(StandardMaterial {
                                         base_color_texture: Some(
-                                            asset_server.load(card.info.name.clone() + ".png"),
+                                            asset_server.load(card.info.art.clone()),
                                         ),
                                         unlit: true,
                                         alpha_mode: AlphaMode::Blend,
@@ -437,7 +646,6 @@ fn on_spawn_card(
                                 })
                                 .insert(NotShadowCaster);
                             let name_mesh = generate_text_mesh(&card.info.name_zh);
-                            let cost_mesh = generate_text_mesh(&card.info.stats.cost.to_string());
                             parent
                                 // use this bundle to change the rotation pivot to the center
                                 .spawn(PbrBundle {
@@ -449,41 +657,60 @@ fn on_spawn_card(
                                 })
                                 .insert(NotShadowCaster);
 
-                            parent
-                                // use this bundle to change the rotation pivot to the center
-                                .spawn(PbrBundle {
-                                    mesh: meshes.add(cost_mesh),
-                                    material: card_data.card_font_material.clone(),
-                                    // transform mesh so that it is in the center
-                                    transform: Transform::from_xyz(-0.5, 0.35, 0.03)
-                                        .with_scale(Vec3::new(2.0, 2.0, 1.0)),
-                                    ..Default::default()
-                                })
-                                .insert(NotShadowCaster);
+                            // equipment shows what it adds to its creature
+                            let labels: &[StatLabel] = match card.card_type() {
+                                CardType::Creature | CardType::Equipment => {
+                                    &[StatLabel::Cost, StatLabel::Attack, StatLabel::Health]
+                                }
+                                CardType::Spell | CardType::Field => &[StatLabel::Cost],
+                            };
+                            for label in labels {
+                                parent
+                                    .spawn(PbrBundle {
+                                        mesh: meshes.add(generate_text_mesh(&label.text(card))),
+                                        material: card_data.card_font_material.clone(),
+                                        transform: label.transform(),
+                                        ..Default::default()
+                                    })
+                                    .insert((*label, NotShadowCaster));
+                            }
+
+                            if card.card_type() == CardType::Creature {
+                                parent
+                                    .spawn(PbrBundle {
+                                        mesh: card_data.mesh.clone(),
+                                        material: card_data.cooldown_material.clone(),
+                                        transform: Transform::from_xyz(0.0, 0.0, 0.04),
+                                        visibility: Visibility::Hidden,
+                                        ..default()
+                                    })
+                                    .insert((CooldownOverlay, NotShadowCaster));
+                                parent
+                                    .spawn(PbrBundle {
+                                        material: card_data.cooldown_font_material.clone(),
+                                        transform: Transform::from_xyz(-0.1, -0.1, 0.05)
+                                            .with_scale(Vec3::new(4.0, 4.0, 1.0)),
+                                        visibility: Visibility::Hidden,
+                                        ..default()
+                                    })
+                                    .insert((CooldownLabel::default(), NotShadowCaster));
+                            }
+
+                            let keywords = card.info.keywords.to_string();
+                            if !keywords.is_empty() {
+                                parent
+                                    .spawn(PbrBundle {
+                                        mesh: meshes.add(generate_text_mesh(&keywords)),
+                                        material: card_data.card_font_material.clone(),
+                                        transform: Transform::from_xyz(-0.33, -0.42, 0.03),
+                                        ..Default::default()
+                                    })
+                                    .insert(NotShadowCaster);
+                            }
                         });
                 });
-                match slot_type {
-                    SlotType::Battlefield => {
-                        // commands.entity(entity).insert(slot::SlotType::Ground);
-                    }
-                    SlotType::Hand => {
-                        hand.try_put_card_into_hand(entity, &mut slots, &mut card);
-                    }
-                    SlotType::Deck => {}
-                    SlotType::Anywhere => {}
-                }
             }
         }
     }
 }
 
-impl From<CardInfo> for Card {
-    fn from(card_info: CardInfo) -> Self {
-        Self {
-            info: card_info,
-            player_id: "todo".to_owned(),
-            animations: default(),
-            slotted_in_slot: default(),
-        }
-    }
-}
@@ -0,0 +1,258 @@
+//! Every card entity changes zones through an [`EvtMoveCard`].
+//!
+//! The rules, the hand and the spawning of cards ask for moves, [`move_cards_between_zones`]
+//! checks them against the slots and performs them in order, and every move that happened
+//! is reported as an [`EvtCardMoved`] for whatever wants to react to it.
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use poplar_core::{CardInstanceId, MatchEvent};
+use thiserror::Error;
+
+use crate::game::{
+    actions::EvtMatch,
+    hand::Hand,
+    player::Player,
+    slot::{Slot, SlotType},
+    LocalData,
+};
+
+use super::Card;
+
+/// Why a card moves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveCause {
+    Drawn,
+    /// brought back from the graveyard or the exile
+    Returned,
+    Played,
+    /// equipment joined the creature in the target slot
+    Attached,
+    /// moved between battlefield slots by its owner or an effect
+    Shifted,
+    Destroyed,
+    /// a spell that resolved, a replaced field card
+    Buried,
+    Exiled,
+    /// the player sorted their hand
+    Rearranged,
+}
+
+/// Asks for a card entity to go from one zone to another.
+/// Zones off the table (deck, graveyard, exile) have no card entities, a card going there is despawned.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct EvtMoveCard {
+    pub card: Entity,
+    pub from_zone: SlotType,
+    pub to_zone: SlotType,
+    /// the slot to go into, `None` lets the hand pick its first free slot
+    pub to_slot: Option<Entity>,
+    pub cause: MoveCause,
+}
+
+/// A move that was checked and performed.
+#[derive(Event, Deref, Debug)]
+pub struct EvtCardMoved(pub EvtMoveCard);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MoveError {
+    #[error("it is not a card")]
+    NoSuchCard,
+    #[error("the card is in the {0:?}")]
+    NotInZone(SlotType),
+    #[error("no slot was given")]
+    NoSlot,
+    #[error("there is no free slot")]
+    NoFreeSlot,
+    #[error("the slot does not take this card")]
+    SlotRefused,
+}
+
+pub fn move_cards_between_zones(
+    mut commands: Commands,
+    mut requests: EventReader<EvtMoveCard>,
+    mut moved: EventWriter<EvtCardMoved>,
+    mut cards: Query<(&mut Card, &mut SlotType)>,
+    mut slots: Query<(&mut Slot, &Player)>,
+    hands: Query<(&Hand, &Player)>,
+) {
+    for request in requests.read() {
+        match move_card(&mut commands, request, &mut cards, &mut slots, &hands) {
+            Ok(()) => {
+                moved.send(EvtCardMoved(*request));
+            }
+            Err(error) => warn!(
+                "can't move {:?} from the {:?} to the {:?}: {}",
+                request.card, request.from_zone, request.to_zone, error
+            ),
+        }
+    }
+}
+
+fn move_card(
+    commands: &mut Commands,
+    request: &EvtMoveCard,
+    cards: &mut Query<(&mut Card, &mut SlotType)>,
+    slots: &mut Query<(&mut Slot, &Player)>,
+    hands: &Query<(&Hand, &Player)>,
+) -> Result<(), MoveError> {
+    let (mut card, mut zone) = cards.get_mut(request.card).map_err(|_| MoveError::NoSuchCard)?;
+    if *zone != request.from_zone {
+        return Err(MoveError::NotInZone(*zone));
+    }
+    let target = match request.to_zone {
+        SlotType::Hand => match request.to_slot {
+            Some(slot) => Some(slot),
+            None => hands
+                .iter()
+                .find(|(_, player)| player.id() == card.player_id)
+                .and_then(|(hand, _)| {
+                    hand.slots().iter().copied().find(|slot| {
+                        slots
+                            .get(*slot)
+                            .is_ok_and(|(slot, _)| slot.slotted_entity().is_none())
+                    })
+                }),
+        }
+        .map(Some)
+        .ok_or(MoveError::NoFreeSlot)?,
+        SlotType::Battlefield | SlotType::Field => Some(request.to_slot.ok_or(MoveError::NoSlot)?),
+        SlotType::Deck | SlotType::Graveyard | SlotType::Exile | SlotType::Anywhere => None,
+    };
+    if let Some(target) = target {
+        let (slot, owner) = slots.get(target).map_err(|_| MoveError::NoSlot)?;
+        let refused = slot.get_type() != request.to_zone
+            || owner.id() != card.player_id
+            || (slot.slotted_entity().is_some_and(|slotted| slotted != request.card)
+                && !slot.accepts(card.card_type()));
+        if refused {
+            return Err(MoveError::SlotRefused);
+        }
+    }
+
+    if let Some(from) = card.slotted_in_slot.take() {
+        if let Ok((mut slot, _)) = slots.get_mut(from) {
+            slot.remove_slotted_entity();
+        }
+    }
+    *zone = request.to_zone;
+    match target {
+        Some(target) => {
+            let (mut slot, _) = slots.get_mut(target).map_err(|_| MoveError::NoSlot)?;
+            if slot.try_slotting_card(request.card, card.card_type()) {
+                card.slotted_in_slot = Some(target);
+            } else {
+                // equipment, it is shown through the stats of its creature
+                commands.entity(request.card).despawn_recursive();
+            }
+        }
+        None => commands.entity(request.card).despawn_recursive(),
+    }
+    Ok(())
+}
+
+// turns what the rules did into moves of the card entities, only our own cards have one
+pub fn card_moves_from_rules(
+    mut events: EventReader<EvtMatch>,
+    mut requests: EventWriter<EvtMoveCard>,
+    local_data: Res<LocalData>,
+    cards: Query<(Entity, &Card, &SlotType)>,
+    slots: Query<(Entity, &Slot, &Player)>,
+) {
+    let entity_of = |instance: CardInstanceId| {
+        cards
+            .iter()
+            .find(|(_, card, _)| card.instance == instance)
+            .map(|(entity, _, zone)| (entity, *zone))
+    };
+    let slot_at = |slot_type: SlotType, index: usize| {
+        slots
+            .iter()
+            .find(|(_, slot, player)| {
+                player.seat() == local_data.seat() && slot.get_type() == slot_type && slot.index() == index
+            })
+            .map(|(entity, slot, _)| (entity, slot.slotted_entity()))
+    };
+    // a card leaving for a pile is sent there by the first event telling so
+    let mut handled = HashSet::new();
+    // where the moves asked for so far leave the cards
+    let mut pending = HashMap::new();
+    for evt in events.read() {
+        let (seat, instance, to_zone, to_slot, cause) = match **evt {
+            MatchEvent::CardPlayed { seat, card, slot } => {
+                (seat, card, SlotType::Battlefield, Some(slot), MoveCause::Played)
+            }
+            MatchEvent::CardMoved { seat, card, to, .. } => {
+                (seat, card, SlotType::Battlefield, Some(to), MoveCause::Shifted)
+            }
+            MatchEvent::CardAttached { seat, card, slot, .. } => {
+                (seat, card, SlotType::Battlefield, Some(slot), MoveCause::Attached)
+            }
+            MatchEvent::FieldPlayed { seat, card } => {
+                // the field card it replaces goes first
+                let replaced = match slot_at(SlotType::Field, 0) {
+                    Some((_, Some(replaced))) if seat == local_data.seat() => Some(replaced),
+                    _ => None,
+                };
+                if let Some(replaced) = replaced {
+                    if let Ok((_, card, _)) = cards.get(replaced) {
+                        handled.insert(card.instance);
+                        pending.insert(replaced, SlotType::Graveyard);
+                        requests.send(EvtMoveCard {
+                            card: replaced,
+                            from_zone: SlotType::Field,
+                            to_zone: SlotType::Graveyard,
+                            to_slot: None,
+                            cause: MoveCause::Buried,
+                        });
+                    }
+                }
+                (seat, card, SlotType::Field, Some(0), MoveCause::Played)
+            }
+            MatchEvent::SpellCast { seat, card } => {
+                (seat, card, SlotType::Graveyard, None, MoveCause::Played)
+            }
+            MatchEvent::CardDestroyed { seat, card, .. } => {
+                (seat, card, SlotType::Graveyard, None, MoveCause::Destroyed)
+            }
+            MatchEvent::CardBuried { seat, card, .. } => {
+                (seat, card, SlotType::Graveyard, None, MoveCause::Buried)
+            }
+            MatchEvent::CardExiled { seat, card, .. } => {
+                (seat, card, SlotType::Exile, None, MoveCause::Exiled)
+            }
+            _ => continue,
+        };
+        if seat != local_data.seat() || (to_slot.is_none() && !handled.insert(instance)) {
+            continue;
+        }
+        let Some((entity, zone)) = entity_of(instance) else {
+            continue;
+        };
+        let from_zone = pending.get(&entity).copied().unwrap_or(zone);
+        let to_slot = match to_slot {
+            Some(index) => match slot_at(to_zone, index) {
+                Some((slot_entity, _)) => Some(slot_entity),
+                None => continue,
+            },
+            None => None,
+        };
+        pending.insert(entity, to_zone);
+        requests.send(EvtMoveCard {
+            card: entity,
+            from_zone,
+            to_zone,
+            to_slot,
+            cause,
+        });
+    }
+}
+
+pub fn log_card_moves(mut moved: EventReader<EvtCardMoved>, cards: Query<&Card>) {
+    for evt in moved.read() {
+        let name = cards.get(evt.card).map(|card| card.info.name.as_str()).unwrap_or("a card");
+        debug!("{} {:?}: {:?} -> {:?}", name, evt.cause, evt.from_zone, evt.to_zone);
+    }
+}
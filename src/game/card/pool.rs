@@ -0,0 +1,62 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    utils::BoxedFuture,
+};
+use poplar_core::{CardPool, CardProblem, PoolFormat};
+use thiserror::Error;
+
+/// The card pool the game plays with, `cards.ron` and `cards.json` are read as well.
+pub const CARD_POOL_PATH: &str = "cards.csv";
+
+/// Reads and checks the card pool, including the portrait of every card.
+#[derive(Default)]
+pub struct CardPoolLoader;
+
+/// The pool file itself could not be read. Problems with its content end up in [`CardPool::errors`].
+#[derive(Error, Debug)]
+pub enum CardPoolLoadError {
+    #[error("could not read the card pool: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("`{0}` is not a card pool, expected a .csv, .ron or .json file")]
+    UnknownFormat(String),
+}
+
+impl AssetLoader for CardPoolLoader {
+    type Asset = CardPool;
+    type Settings = ();
+    type Error = CardPoolLoadError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<CardPool, CardPoolLoadError>> {
+        Box::pin(async move {
+            let path = load_context.path().to_path_buf();
+            let format = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(PoolFormat::from_extension)
+                .ok_or_else(|| CardPoolLoadError::UnknownFormat(path.display().to_string()))?;
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            let mut pool = CardPool::parse(format, &bytes);
+            let mut missing = Vec::new();
+            for (row, card) in pool.rows() {
+                if load_context.read_asset_bytes(card.art.as_str()).await.is_err() {
+                    missing.push((row, card.art.clone()));
+                }
+            }
+            for (row, art) in missing {
+                pool.report(row, "art", CardProblem::MissingPortrait(art));
+            }
+            Ok(pool)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cards.csv", "cards.ron", "cards.json"]
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use poplar_core::{CardRegistry, Decklist};
+
+/// Where the decklists are saved, one `<name>.ron` each. The format is described in
+/// [`poplar_core::decklist`].
+pub const DECKS_DIR: &str = "decks";
+
+/// The saved decklists and the one picked to play with.
+#[derive(Resource, Default)]
+pub struct Decklists {
+    lists: Vec<Decklist>,
+    selected: Option<String>,
+}
+
+impl FromWorld for Decklists {
+    fn from_world(_world: &mut World) -> Self {
+        Self::load(Path::new(DECKS_DIR))
+    }
+}
+
+impl Decklists {
+    /// Reads every decklist in `dir`, the ones that don't parse are skipped.
+    pub fn load(dir: &Path) -> Self {
+        // nothing saved yet
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Self::default();
+        };
+        let mut lists = Vec::new();
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|extension| extension.to_str()) != Some("ron") {
+                continue;
+            }
+            let deck = fs::read_to_string(&path)
+                .map_err(|error| error.to_string())
+                .and_then(|text| Decklist::from_ron(&text).map_err(|error| error.to_string()));
+            match deck {
+                Ok(deck) => lists.push(deck),
+                Err(error) => warn!("Skipping decklist {}: {}", path.display(), error),
+            }
+        }
+        lists.sort_by(|a, b| a.name.cmp(&b.name));
+        let selected = lists.first().map(|deck| deck.name.clone());
+        Self { lists, selected }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Decklist> {
+        self.lists.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Decklist> {
+        self.lists.iter().find(|deck| deck.name == name)
+    }
+
+    pub fn selected(&self) -> Option<&Decklist> {
+        self.selected.as_deref().and_then(|name| self.get(name))
+    }
+
+    pub fn select(&mut self, name: &str) {
+        if self.get(name).is_some() {
+            self.selected = Some(name.to_string());
+        }
+    }
+
+    /// Writes `deck` to disk, over the saved deck of the same name, and picks it to play with.
+    /// Check the deck first, its name becomes the file name.
+    pub fn save(&mut self, deck: Decklist) -> io::Result<()> {
+        fs::create_dir_all(DECKS_DIR)?;
+        fs::write(Self::path(&deck.name), deck.to_ron())?;
+        self.selected = Some(deck.name.clone());
+        match self.lists.iter_mut().find(|saved| saved.name == deck.name) {
+            Some(saved) => *saved = deck,
+            None => {
+                self.lists.push(deck);
+                self.lists.sort_by(|a, b| a.name.cmp(&b.name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The picked deck if it still fits the card pool, the starter deck otherwise.
+    pub fn deck_for_match(&self, registry: &CardRegistry) -> Decklist {
+        match self.selected() {
+            Some(deck) if deck.check(registry).is_ok() => deck.clone(),
+            _ => Decklist::starter(registry),
+        }
+    }
+
+    fn path(name: &str) -> PathBuf {
+        Path::new(DECKS_DIR).join(format!("{}.ron", name))
+    }
+}
@@ -1,59 +1,49 @@
-use bevy::{
-    prelude::*,
-    render::{
-        render_asset::RenderAssetUsages,
-        render_resource::{Extent3d, TextureDimension, TextureFormat},
-    },
-};
-use bevy_inspector_egui::prelude::*;
-use bevy_inspector_egui::quick::ResourceInspectorPlugin;
+use bevy::prelude::*;
+use poplar_core::{Action, CardInstance, MatchEvent, MatchState, Seat};
 
 use crate::AppState;
 
-use super::{card::CardInfo, hand::Hand, systemsets::PlayingSets};
+pub mod decklists;
+use decklists::Decklists;
+
+use super::{
+    actions::{listen_actions, EvtMatch, PlayerActionEvt},
+    systemsets::PlayingSets,
+    LocalData,
+};
 pub struct DeckPlugin;
 
 impl Plugin for DeckPlugin {
     fn build(&self, app: &mut App) {
         app
+            .init_resource::<Decklists>()
             .add_event::<EvtDrawCardFromDeck>()
             .add_systems(OnEnter(AppState::Playing), deck_setup)
+            .add_systems(OnExit(AppState::Playing), despawn_deck)
             .add_systems(Update, (
                 on_deck_spawn_card,
-            ).in_set(PlayingSets::Main));
+                on_card_drawn.after(listen_actions),
+            ).in_set(PlayingSets::Main).run_if(resource_exists::<MatchState>));
     }
 }
 
+/// The pile standing for a player's deck, the cards themselves live in the `MatchState`.
 #[derive(Component)]
-pub struct Deck {
-    slots: Vec<CardInfo>,
-}
-
-impl Deck {
-    fn pop(&mut self) -> Option<CardInfo> {
-        self.slots.pop()
-    }
-
-    fn new() -> Self {
-        Deck { slots: Vec::new() }
-    }
+pub struct DeckPile {
+    pub seat: Seat,
 }
 
 #[derive(Event)]
 pub struct EvtDrawCardFromDeck {
-    pub card_infos: Vec<CardInfo>,
+    pub cards: Vec<CardInstance>,
 }
 
 fn deck_setup(
     mut cmds: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    card_infos: Res<Assets<CardInfo>>,
+    local_data: Res<LocalData>,
 ) {
-    let mut deck = Deck::new();
-    for (_, card_info) in card_infos.iter() {
-        deck.slots.push(card_info.clone());
-    }
     cmds.spawn((
         PbrBundle {
             mesh: meshes.add(Cuboid::new(1.0, 1.5, 1.0)),
@@ -62,24 +52,48 @@ fn deck_setup(
             ..default()
         },
         Name::new("Deck"),
-        deck,
+        DeckPile {
+            seat: local_data.seat,
+        },
     ));
 }
 
+fn despawn_deck(mut cmds: Commands, piles: Query<Entity, With<DeckPile>>) {
+    for entity in piles.iter() {
+        cmds.entity(entity).despawn_recursive();
+    }
+}
+
 // press space and spawn a card
 fn on_deck_spawn_card(
     input: Res<ButtonInput<KeyCode>>,
-    mut evt: EventWriter<EvtDrawCardFromDeck>,
-    mut deck: Query<&mut Deck>,
+    local_data: Res<LocalData>,
+    mut actions: EventWriter<PlayerActionEvt>,
 ) {
     if input.just_pressed(KeyCode::Space) {
-        let mut deck = deck.single_mut();
-        let mut card_infos = Vec::new();
-        if let Some(card_info) = deck.pop() {
-            card_infos.push(card_info);
-        }
-        evt.send(EvtDrawCardFromDeck {
-            card_infos
+        actions.send(PlayerActionEvt {
+            seat: local_data.seat,
+            action: Action::DrawCard,
         });
     }
-} 
\ No newline at end of file
+}
+
+fn on_card_drawn(
+    mut events: EventReader<EvtMatch>,
+    mut evt: EventWriter<EvtDrawCardFromDeck>,
+    local_data: Res<LocalData>,
+    match_state: Res<MatchState>,
+) {
+    let cards: Vec<CardInstance> = events
+        .read()
+        .filter_map(|event| match **event {
+            MatchEvent::CardDrawn { seat, card } if seat == local_data.seat => {
+                match_state.card(card).cloned()
+            }
+            _ => None,
+        })
+        .collect();
+    if !cards.is_empty() {
+        evt.send(EvtDrawCardFromDeck { cards });
+    }
+}
@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+use poplar_core::Seat;
+
+pub struct GoalPlugin;
+
+impl Plugin for GoalPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Seat>()
+            .register_type::<Goal>();
+    }
+}
+
+/// The edge of the table a player defends, put on a `_sensor` proxy in Blender as `Goal: (seat: One)`.
+/// The rules tell when a ball gets past it, the sensor only marks it in the scene.
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+pub struct Goal {
+    pub seat: Seat,
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use poplar_core::{MatchEvent, MatchState, Seat, Zone};
+
+use crate::AppState;
+
+use super::{
+    actions::{listen_actions, EvtMatch},
+    card::{Card, EvtSpawnCard},
+    slot::SlotType,
+    systemsets::PlayingSets,
+    LocalData,
+};
+
+pub struct GraveyardPlugin;
+
+impl Plugin for GraveyardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_piles)
+            .add_systems(OnExit(AppState::Playing), despawn_piles)
+            .add_systems(
+                Update,
+                (
+                    on_card_returned.after(listen_actions),
+                    update_piles.run_if(resource_changed::<MatchState>),
+                    (toggle_pile_list, update_pile_list).chain(),
+                )
+                    .in_set(PlayingSets::Main)
+                    .run_if(resource_exists::<MatchState>),
+            );
+    }
+}
+
+/// The graveyard or the exile of a player, which of the two is its `SlotType`.
+/// The cards themselves live in the `MatchState`, the pile grows with them.
+#[derive(Component)]
+pub struct ZonePile {
+    pub seat: Seat,
+}
+
+/// Lists what is in the piles, toggled with `G`.
+#[derive(Component)]
+struct PileList;
+
+const CARD_THICKNESS: f32 = 0.02;
+
+fn spawn_piles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    local_data: Res<LocalData>,
+) {
+    let mesh = meshes.add(Cuboid::new(Card::ASPECT_RATIO, 1.0, CARD_THICKNESS));
+    for (slot_type, color, y) in [
+        (SlotType::Graveyard, Color::rgb_u8(90, 90, 90), -2.4),
+        (SlotType::Exile, Color::rgb_u8(150, 90, 200), -1.2),
+    ] {
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(color),
+                transform: Transform::from_xyz(3.1, y, 0.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            Name::new(format!("{:?}", slot_type)),
+            ZonePile {
+                seat: local_data.seat(),
+            },
+            slot_type,
+        ));
+    }
+    commands.spawn((
+        TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PileList,
+    ));
+}
+
+fn despawn_piles(
+    mut commands: Commands,
+    piles: Query<Entity, Or<(With<ZonePile>, With<PileList>)>>,
+) {
+    for entity in piles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn update_piles(
+    match_state: Res<MatchState>,
+    mut piles: Query<(&ZonePile, &SlotType, &mut Transform, &mut Visibility)>,
+) {
+    for (pile, slot_type, mut transform, mut visibility) in piles.iter_mut() {
+        let player = match_state.player(pile.seat);
+        let count = match slot_type.zone() {
+            Some(Zone::Graveyard) => player.graveyard.len(),
+            Some(Zone::Exile) => player.exile.len(),
+            _ => continue,
+        };
+        let height = count as f32 * CARD_THICKNESS;
+        transform.scale.z = count.max(1) as f32;
+        transform.translation.z = height / 2.0;
+        *visibility = if count == 0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+fn toggle_pile_list(
+    input: Res<ButtonInput<KeyCode>>,
+    mut lists: Query<&mut Visibility, With<PileList>>,
+) {
+    if input.just_pressed(KeyCode::KeyG) {
+        for mut visibility in lists.iter_mut() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Inherited,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+fn update_pile_list(
+    match_state: Res<MatchState>,
+    local_data: Res<LocalData>,
+    mut lists: Query<(Ref<Visibility>, &mut Text), With<PileList>>,
+) {
+    for (visibility, mut text) in lists.iter_mut() {
+        if *visibility == Visibility::Hidden || !(visibility.is_changed() || match_state.is_changed()) {
+            continue;
+        }
+        let player = match_state.player(local_data.seat());
+        let mut lines = Vec::new();
+        for (title, pile) in [("Graveyard", &player.graveyard), ("Exile", &player.exile)] {
+            lines.push(format!("{} ({})", title, pile.len()));
+            // the top of the pile first
+            lines.extend(pile.search(|_| true).map(|card| format!("  {}", card.info.name)));
+        }
+        *text = Text::from_section(
+            lines.join("\n"),
+            TextStyle {
+                font_size: 24.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        );
+    }
+}
+
+// cards brought back from a pile into the hand show up there again
+fn on_card_returned(
+    mut events: EventReader<EvtMatch>,
+    mut spawn_card: EventWriter<EvtSpawnCard>,
+    local_data: Res<LocalData>,
+    match_state: Res<MatchState>,
+) {
+    for evt in events.read() {
+        let MatchEvent::CardReturned { seat, card, from, to: Zone::Hand } = **evt else {
+            continue;
+        };
+        if seat != local_data.seat() {
+            continue;
+        }
+        if let Some(card) = match_state.card(card) {
+            spawn_card.send(EvtSpawnCard {
+                from: from.into(),
+                slot_type: SlotType::Hand,
+                card: card.clone(),
+            });
+        }
+    }
+}
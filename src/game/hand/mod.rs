@@ -26,6 +26,7 @@ impl Plugin for HandPlugin {
     fn build(&self, app: &mut App) {
         // TODO
         app.add_systems(OnEnter(AppState::Playing), spawn_hand)
+            .add_systems(OnExit(AppState::Playing), despawn_hands)
             .add_systems(
                 Update,
                 (on_spawn_hand, deck_draw_card).in_set(PlayingSets::Main),
@@ -78,21 +79,9 @@ fn recalc_hand_transform(
 }
 
 impl Hand {
-    pub fn try_put_card_into_hand(
-        &mut self,
-        card_entity: Entity,
-        slots: &mut Query<&mut Slot>,
-        card: &mut Card,
-    ) -> bool {
-        for slot_entity in self.slots.iter() {
-            if let Ok(mut slot) = slots.get_mut(*slot_entity) {
-                if slot.try_slotting_card(card_entity) {
-                    card.slotted_in_slot = Some(*slot_entity);
-                    return true;
-                }
-            }
-        }
-        false
+    /// In the order they are laid out, cards moving into the hand take the first free one.
+    pub fn slots(&self) -> &[Entity] {
+        &self.slots
     }
 }
 
@@ -107,37 +96,53 @@ fn deck_draw_card(
     mut evt_spawn_card: EventWriter<EvtSpawnCard>,
 ) {
     for evt in evts.read() {
-        for card_info in evt.card_infos.iter() {
+        for card in evt.cards.iter() {
             evt_spawn_card.send(EvtSpawnCard {
+                from: SlotType::Deck,
                 slot_type: SlotType::Hand,
-                card_info: card_info.clone(),
+                card: card.clone(),
             });
         }
     }
 }
 
-fn test_spawn_hand(mut commands: Commands, input: Res<ButtonInput<KeyCode>>) {
-    if input.just_pressed(KeyCode::KeyA) {
+fn test_spawn_hand(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    local_data: Res<LocalData>,
+) {
+    // `A` steers the paddle
+    if input.just_pressed(KeyCode::KeyN) {
         commands.spawn(SlotBundle {
             slot: Slot::new(SlotType::Hand, None),
+            player: Player::local(&local_data),
             ..default()
         });
     }
 }
 
-fn spawn_hand(mut commands: Commands) {
+fn spawn_hand(mut commands: Commands, local_data: Res<LocalData>) {
+    let player = Player::local(&local_data);
     commands.spawn(HandBundle {
         hand: Hand { slots: vec![] },
-        player: Player::default(),
+        player,
     });
     for _i in 0..5 {
         commands.spawn(SlotBundle {
             slot: Slot::new(SlotType::Hand, None),
+            player,
             ..default()
         });
     }
 }
 
+// the slots of the hand go with the other slots
+fn despawn_hands(mut commands: Commands, hands: Query<Entity, With<Hand>>) {
+    for entity in hands.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
 fn on_spawn_hand(
     mut commands: Commands,
     local_data: Res<LocalData>,
@@ -0,0 +1,125 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::game::card::CardPool;
+use crate::game::menu::ButtonColors;
+use crate::{AppState, CardsHandle};
+
+pub fn card_pool_error_plugin(app: &mut App) {
+    app
+        .add_systems(OnEnter(AppState::CardPoolError), on_card_pool_error)
+        .add_systems(Update, (click_button).run_if(in_state(AppState::CardPoolError)))
+        .add_systems(OnExit(AppState::CardPoolError), cleanup_menu);
+}
+
+#[derive(Component)]
+struct CardPoolErrorMenu;
+
+#[derive(Component)]
+struct QuitButton;
+
+fn on_card_pool_error(
+    mut commands: Commands,
+    cards: Res<CardsHandle>,
+    card_pools: Res<Assets<CardPool>>,
+) {
+    // without a pool the file itself could not be read, the log says why
+    let errors: Vec<String> = match card_pools.get(&cards.0) {
+        Some(pool) => pool.errors().iter().map(ToString::to_string).collect(),
+        None => vec!["the card pool could not be read".to_string()],
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(5.),
+                    ..default()
+                },
+                ..default()
+            },
+            CardPoolErrorMenu,
+        ))
+        .with_children(|children| {
+            children.spawn(TextBundle::from_section(
+                "The card pool is invalid",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.3, 0.3),
+                    ..default()
+                },
+            ));
+            for error in errors {
+                children.spawn(TextBundle::from_section(
+                    error,
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                        ..default()
+                    },
+                ));
+            }
+
+            let button_colors = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(140.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors,
+                    QuitButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Quit",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn click_button(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &ButtonColors),
+        (Changed<Interaction>, With<QuitButton>),
+    >,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, mut color, button_colors) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                exit.send(AppExit);
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn cleanup_menu(
+    mut commands: Commands,
+    menu: Query<Entity, With<CardPoolErrorMenu>>,
+) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,283 @@
+use bevy::{prelude::*, window::ReceivedCharacter};
+use poplar_core::{CardId, Decklist};
+
+use crate::AppState;
+use crate::game::card::CardRegistry;
+use crate::game::deck::decklists::Decklists;
+use crate::game::menu::ButtonColors;
+use crate::game::menu::ChangeState;
+
+pub fn deck_builder_plugin(app: &mut App) {
+    app.init_resource::<DeckBuilder>()
+        .add_systems(OnEnter(AppState::DeckBuilder), (start_editing, spawn_menu).chain())
+        .add_systems(
+            Update,
+            (
+                type_name,
+                click_button,
+                // the saved decks are buttons, rebuild once one is saved or picked
+                spawn_menu.run_if(resource_changed::<Decklists>),
+                update_builder_text,
+            )
+                .chain()
+                .run_if(in_state(AppState::DeckBuilder)),
+        )
+        .add_systems(OnExit(AppState::DeckBuilder), cleanup_menu);
+}
+
+/// The deck being edited, only saved when asked to.
+#[derive(Resource, Default)]
+pub struct DeckBuilder {
+    pub deck: Decklist,
+    pub status: String,
+}
+
+#[derive(Component)]
+struct DeckBuilderMenu;
+
+#[derive(Component)]
+struct DeckText;
+
+#[derive(Component)]
+struct CopiesText(CardId);
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component, Clone)]
+enum ButtonType {
+    Add(CardId),
+    Remove(CardId),
+    Load(String),
+    New,
+    Save,
+    Back,
+}
+
+fn spawn_button(children: &mut ChildBuilder, label: &str, width: f32, button_type: ButtonType) {
+    let button_colors = ButtonColors::default();
+    let mut button = children.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(width),
+                height: Val::Px(40.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            background_color: button_colors.normal.into(),
+            ..Default::default()
+        },
+        button_colors,
+        button_type.clone(),
+    ));
+    if let ButtonType::Back = button_type {
+        button.insert(ChangeState(AppState::MainMenu));
+    }
+    button.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 30.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ));
+    });
+}
+
+fn spawn_text(children: &mut ChildBuilder, marker: impl Component, font_size: f32) {
+    children.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ),
+        marker,
+    ));
+}
+
+fn spawn_row(children: &mut ChildBuilder, spawn: impl FnOnce(&mut ChildBuilder)) {
+    children
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(10.),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(spawn);
+}
+
+fn start_editing(decklists: Res<Decklists>, mut builder: ResMut<DeckBuilder>) {
+    *builder = DeckBuilder {
+        deck: decklists.selected().cloned().unwrap_or_else(|| Decklist::new("my deck")),
+        status: "Type to rename the deck".to_string(),
+    };
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    registry: Res<CardRegistry>,
+    decklists: Res<Decklists>,
+    menu: Query<Entity, With<DeckBuilderMenu>>,
+) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(5.),
+                    ..default()
+                },
+                ..default()
+            },
+            DeckBuilderMenu,
+        ))
+        .with_children(|children| {
+            spawn_text(children, DeckText, 40.0);
+            for card in registry.iter() {
+                spawn_row(children, |row| {
+                    spawn_button(row, "-", 40.0, ButtonType::Remove(card.id.clone()));
+                    spawn_text(row, CopiesText(card.id.clone()), 30.0);
+                    spawn_button(row, "+", 40.0, ButtonType::Add(card.id.clone()));
+                });
+            }
+            spawn_row(children, |row| {
+                for deck in decklists.iter() {
+                    let picked = decklists.selected().map(|selected| &selected.name) == Some(&deck.name);
+                    let label = if picked { format!("* {}", deck.name) } else { deck.name.clone() };
+                    spawn_button(row, &label, 160.0, ButtonType::Load(deck.name.clone()));
+                }
+            });
+            spawn_row(children, |row| {
+                spawn_button(row, "New", 140.0, ButtonType::New);
+                spawn_button(row, "Save", 140.0, ButtonType::Save);
+                spawn_button(row, "Back", 140.0, ButtonType::Back);
+            });
+            spawn_text(children, StatusText, 24.0);
+        });
+}
+
+// the deck name is typed straight into the builder
+fn type_name(
+    mut builder: ResMut<DeckBuilder>,
+    mut characters: EventReader<ReceivedCharacter>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    for character in characters.read() {
+        for c in character.char.chars() {
+            if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' {
+                builder.deck.name.push(c);
+            }
+        }
+    }
+    if input.just_pressed(KeyCode::Backspace) {
+        builder.deck.name.pop();
+    }
+}
+
+fn click_button(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut builder: ResMut<DeckBuilder>,
+    mut decklists: ResMut<Decklists>,
+    registry: Res<CardRegistry>,
+    mut interaction_query: Query<
+        (
+            &ButtonType,
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&ChangeState>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button_type, interaction, mut color, button_colors, change_state) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                if let Some(state) = change_state {
+                    next_state.set(state.0.clone());
+                }
+                match button_type {
+                    ButtonType::Add(card) => {
+                        if let Err(error) = builder.deck.add(card) {
+                            builder.status = error.to_string();
+                        }
+                    }
+                    ButtonType::Remove(card) => {
+                        builder.deck.remove(card);
+                    }
+                    ButtonType::Load(name) => {
+                        if let Some(deck) = decklists.get(name) {
+                            builder.deck = deck.clone();
+                            builder.status = format!("Playing with {}", name);
+                        }
+                        decklists.select(name);
+                    }
+                    ButtonType::New => {
+                        builder.deck = Decklist::new("new deck");
+                    }
+                    ButtonType::Save => {
+                        let deck = builder.deck.clone();
+                        let name = deck.name.clone();
+                        builder.status = match deck.check(&registry) {
+                            Ok(()) => match decklists.save(deck) {
+                                Ok(()) => format!("Saved, playing with {}", name),
+                                Err(error) => format!("Can't save: {}", error),
+                            },
+                            Err(error) => format!("Can't save: {}", error),
+                        };
+                    }
+                    ButtonType::Back => {}
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn update_builder_text(
+    builder: Res<DeckBuilder>,
+    registry: Res<CardRegistry>,
+    mut deck_text: Query<&mut Text, (With<DeckText>, Without<CopiesText>, Without<StatusText>)>,
+    mut copies_text: Query<(&mut Text, &CopiesText), (Without<DeckText>, Without<StatusText>)>,
+    mut status_text: Query<&mut Text, (With<StatusText>, Without<DeckText>, Without<CopiesText>)>,
+) {
+    for mut text in deck_text.iter_mut() {
+        text.sections[0].value = format!("{}  {}/{}", builder.deck.name, builder.deck.len(), Decklist::SIZE);
+    }
+    for (mut text, CopiesText(card)) in copies_text.iter_mut() {
+        let name = registry.get(card).map(|info| info.name_zh.as_str()).unwrap_or_default();
+        text.sections[0].value = format!("{} x{}", name, builder.deck.copies(card));
+    }
+    for mut text in status_text.iter_mut() {
+        text.sections[0].value = builder.status.clone();
+    }
+}
+
+fn cleanup_menu(
+    mut commands: Commands,
+    menu: Query<Entity, With<DeckBuilderMenu>>,
+) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
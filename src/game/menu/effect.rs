@@ -4,7 +4,7 @@ use bevy:: prelude::*;
 pub fn effect_plugin(app: &mut App) {
     app.add_systems(OnEnter(AppState::Playing), setup_effect)
         .add_systems(Update, (disappearing).run_if(in_state(AppState::Playing)))
-        // .add_systems(OnExit(AppState::Playing), cleanup_effect)
+        .add_systems(OnExit(AppState::Playing), cleanup_effect)
         ;
 }
 
@@ -38,6 +38,13 @@ pub struct Disappearing {
     pub timer: Timer,
 }
 
+// whatever had not faded out yet when the match ended
+fn cleanup_effect(mut commands: Commands, query: Query<Entity, With<Disappearing>>) {
+    for e in query.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+}
+
 fn disappearing(
     mut commands: Commands,
     mut query: Query<(Entity, &mut BackgroundColor, &mut Disappearing)>,
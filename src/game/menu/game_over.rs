@@ -1,9 +1,12 @@
-
 use bevy:: prelude::*;
 
 use crate::AppState;
+use crate::game::actions::record::MatchRecord;
+use crate::game::menu::room::Room;
 use crate::game::menu::ButtonColors;
 use crate::game::menu::ChangeState;
+use crate::game::net::{client::{self, ConnectionStatus}, server};
+use crate::game::LocalData;
 
 pub fn game_over_menu_plugin(app: &mut App) {
     app
@@ -23,9 +26,82 @@ enum ButtonType {
     ToMenu,
 }
 
+fn spawn_button(children: &mut ChildBuilder, label: &str, button_type: ButtonType, state: AppState) {
+    let button_colors = ButtonColors::default();
+    children
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(50.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: button_colors.normal.into(),
+                ..Default::default()
+            },
+            button_colors,
+            ChangeState(state),
+            button_type,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn spawn_text(children: &mut ChildBuilder, text: String, font_size: f32) {
+    children.spawn(TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size,
+            color: Color::rgb(0.9, 0.9, 0.9),
+            ..default()
+        },
+    ));
+}
+
 fn on_game_over(
     mut commands: Commands,
+    record: Res<MatchRecord>,
+    local_data: Res<LocalData>,
+    status: Res<ConnectionStatus>,
 ) {
+    let seat = local_data.seat();
+    let title = match record.result.map(|result| result.winner) {
+        Some(Some(winner)) if winner == seat => "You won",
+        Some(Some(_)) => "You lost",
+        Some(None) => "Draw",
+        None => "Match over",
+    };
+    // the loser is the one the reason is about
+    let reason = match record.result {
+        Some(result) => match result.winner {
+            Some(winner) if winner == seat => format!("Your opponent {}", result.reason),
+            Some(_) => format!("You {}", result.reason),
+            None => format!("Both players {}", result.reason),
+        },
+        None => String::new(),
+    };
+    let seconds = record.duration.as_secs();
+    let mut stats = vec![format!("{}:{:02} played", seconds / 60, seconds % 60)];
+    for (who, seat) in [("You", seat), ("Opponent", seat.opponent())] {
+        let seat_record = record.seat(seat);
+        stats.push(format!(
+            "{}: {} cards played, {} creatures lost, {} damage taken",
+            who, seat_record.cards_played, seat_record.creatures_lost, seat_record.damage_taken
+        ));
+    }
+    // online the next match is set up in the room again
+    let next_match = if status.is_connected() { AppState::RoomMenu } else { AppState::Playing };
+
     commands
         .spawn((
             NodeBundle {
@@ -43,68 +119,19 @@ fn on_game_over(
             GameOverMenu,
         ))
         .with_children(|children| {
-            let button_colors = ButtonColors::default();
-            children
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            width: Val::Px(140.0),
-                            height: Val::Px(50.0),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..Default::default()
-                        },
-                        background_color: button_colors.normal.into(),
-                        ..Default::default()
-                    },
-                    button_colors,
-                    ChangeState(AppState::Playing),
-                    ButtonType::Play,
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "One More Game",
-                        TextStyle {
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..default()
-                        },
-                    ));
-                });
-                
-            let button_colors2 = ButtonColors::default();
-            children
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            width: Val::Px(140.0),
-                            height: Val::Px(50.0),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..Default::default()
-                        },
-                        background_color: button_colors2.normal.into(),
-                        ..Default::default()
-                    },
-                    button_colors2,
-                    ButtonType::ToMenu,
-                    ChangeState(AppState::MainMenu),
-                ))
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        "Back to Menu",
-                        TextStyle {
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                            ..default()
-                        },
-                    ));
-                });
+            spawn_text(children, title.to_string(), 60.0);
+            spawn_text(children, reason, 30.0);
+            spawn_text(children, stats.join("\n"), 24.0);
+            spawn_button(children, "One More Game", ButtonType::Play, next_match);
+            spawn_button(children, "Back to Menu", ButtonType::ToMenu, AppState::MainMenu);
         });
 }
 
 fn click_button(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
+    mut room: ResMut<Room>,
+    mut local_data: ResMut<LocalData>,
     mut interaction_query: Query<
         (
             &ButtonType,
@@ -116,12 +143,24 @@ fn click_button(
         (Changed<Interaction>, With<Button>),
     >,
 ) {
-    for (_button_type, interaction, mut color, button_colors, change_state) in &mut interaction_query {
+    for (button_type, interaction, mut color, button_colors, change_state) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
                 if let Some(state) = change_state {
                     next_state.set(state.0.clone());
                 }
+                match button_type {
+                    ButtonType::Play => {
+                        // the server put everyone back in the lobby, not ready
+                        room.ready = false;
+                    }
+                    ButtonType::ToMenu => {
+                        client::disconnect(&mut commands);
+                        server::stop_hosting(&mut commands);
+                        *room = Room::default();
+                        *local_data = LocalData::offline();
+                    }
+                }
             }
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
@@ -134,11 +173,11 @@ fn click_button(
 }
 
 fn cleanup_menu(
-    mut commands: Commands, 
+    mut commands: Commands,
     menu: Query<Entity, With<GameOverMenu>>,
 ) {
     for entity in menu.iter() {
         commands.entity(entity).despawn_recursive();
     }
-     
-}
\ No newline at end of file
+
+}
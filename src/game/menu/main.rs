@@ -17,6 +17,8 @@ struct Menu;
 #[derive(Component)]
 enum ButtonType {
     Play,
+    Room,
+    Decks,
     Quit
 }
 
@@ -67,7 +69,65 @@ fn setup_menu(mut commands: Commands) {
                         },
                     ));
                 });
+
+            let button_colors_room = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(140.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors_room.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors_room,
+                    ChangeState(AppState::RoomMenu),
+                    ButtonType::Room,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Online",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
                 
+            let button_colors_decks = ButtonColors::default();
+            children
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(140.0),
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        background_color: button_colors_decks.normal.into(),
+                        ..Default::default()
+                    },
+                    button_colors_decks,
+                    ChangeState(AppState::DeckBuilder),
+                    ButtonType::Decks,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Decks",
+                        TextStyle {
+                            font_size: 40.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                            ..default()
+                        },
+                    ));
+                });
+
             let button_colors2 = ButtonColors::default();
             children
                 .spawn((
@@ -130,7 +190,7 @@ fn click_play_button(
                     ButtonType::Quit => {
                         exit.send(AppExit);
                     }
-                    ButtonType::Play => {
+                    ButtonType::Play | ButtonType::Room | ButtonType::Decks => {
                         
                     }
                 }
@@ -6,6 +6,8 @@ pub mod main;
 pub mod room;
 pub mod game_over;
 pub mod effect;
+pub mod card_pool_error;
+pub mod deck_builder;
 
 pub struct MenuPlugin;
 
@@ -34,8 +36,11 @@ impl Plugin for MenuPlugin {
         app
             .add_plugins((
                 main::main_menu_plugin,
+                room::room_menu_plugin,
                 game_over::game_over_menu_plugin,
                 effect::effect_plugin,
+                card_pool_error::card_pool_error_plugin,
+                deck_builder::deck_builder_plugin,
             ));
     }
 }
\ No newline at end of file
@@ -0,0 +1,321 @@
+use std::net::SocketAddr;
+
+use bevy::{prelude::*, window::ReceivedCharacter};
+use bevy_renet::renet::RenetClient;
+use poplar_core::MatchState;
+
+use crate::AppState;
+use crate::game::card::CardRegistry;
+use crate::game::deck::decklists::Decklists;
+use crate::game::menu::ButtonColors;
+use crate::game::menu::ChangeState;
+use crate::game::net::{
+    client::{self, ConnectionStatus, EvtServerMessage},
+    protocol::{encode, ClientChannel, ClientMessage, LobbyEntry, ServerMessage},
+    server,
+};
+use crate::game::LocalData;
+
+pub fn room_menu_plugin(app: &mut App) {
+    app.init_resource::<Room>()
+        .add_systems(OnEnter(AppState::RoomMenu), setup_menu)
+        .add_systems(
+            Update,
+            (type_address, click_button, on_server_message, update_room_text)
+                .chain()
+                .run_if(in_state(AppState::RoomMenu)),
+        )
+        .add_systems(OnExit(AppState::RoomMenu), cleanup_menu);
+}
+
+/// What the room screen knows about the match being set up.
+#[derive(Resource)]
+pub struct Room {
+    pub address: String,
+    pub status: String,
+    pub lobby: Vec<LobbyEntry>,
+    pub my_id: Option<u64>,
+    pub ready: bool,
+}
+
+impl Default for Room {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:5000".to_string(),
+            status: "Host a match or join one".to_string(),
+            lobby: Vec::new(),
+            my_id: None,
+            ready: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct RoomMenu;
+
+#[derive(Component)]
+struct AddressText;
+
+#[derive(Component)]
+struct PlayersText;
+
+#[derive(Component)]
+struct StatusText;
+
+#[derive(Component, Clone, Copy)]
+enum ButtonType {
+    Host,
+    Join,
+    Ready,
+    Back,
+}
+
+fn spawn_button(children: &mut ChildBuilder, label: &str, button_type: ButtonType) {
+    let button_colors = ButtonColors::default();
+    let mut button = children.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(140.0),
+                height: Val::Px(50.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            background_color: button_colors.normal.into(),
+            ..Default::default()
+        },
+        button_colors,
+        button_type,
+    ));
+    if let ButtonType::Back = button_type {
+        button.insert(ChangeState(AppState::MainMenu));
+    }
+    button.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            label,
+            TextStyle {
+                font_size: 40.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ));
+    });
+}
+
+fn spawn_text(children: &mut ChildBuilder, marker: impl Component, font_size: f32) {
+    children.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size,
+                color: Color::rgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ),
+        marker,
+    ));
+}
+
+fn setup_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(5.),
+                    ..default()
+                },
+                ..default()
+            },
+            RoomMenu,
+        ))
+        .with_children(|children| {
+            spawn_text(children, AddressText, 40.0);
+            spawn_button(children, "Host", ButtonType::Host);
+            spawn_button(children, "Join", ButtonType::Join);
+            spawn_button(children, "Ready", ButtonType::Ready);
+            spawn_button(children, "Back", ButtonType::Back);
+            spawn_text(children, PlayersText, 30.0);
+            spawn_text(children, StatusText, 24.0);
+        });
+}
+
+// the address is typed straight into the room screen
+fn type_address(
+    mut room: ResMut<Room>,
+    mut characters: EventReader<ReceivedCharacter>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    for character in characters.read() {
+        for c in character.char.chars() {
+            if c.is_ascii_alphanumeric() || c == '.' || c == ':' {
+                room.address.push(c);
+            }
+        }
+    }
+    if input.just_pressed(KeyCode::Backspace) {
+        room.address.pop();
+    }
+}
+
+fn click_button(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut room: ResMut<Room>,
+    mut local_data: ResMut<LocalData>,
+    mut client: Option<ResMut<RenetClient>>,
+    registry: Res<CardRegistry>,
+    decklists: Res<Decklists>,
+    mut interaction_query: Query<
+        (
+            &ButtonType,
+            &Interaction,
+            &mut BackgroundColor,
+            &ButtonColors,
+            Option<&ChangeState>,
+        ),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button_type, interaction, mut color, button_colors, change_state) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                if let Some(state) = change_state {
+                    next_state.set(state.0.clone());
+                }
+                match button_type {
+                    ButtonType::Host | ButtonType::Join => {
+                        let Ok(addr) = room.address.parse::<SocketAddr>() else {
+                            room.status = format!("{} is not an ip:port address", room.address);
+                            continue;
+                        };
+                        let mut server_addr = addr;
+                        if let ButtonType::Host = button_type {
+                            if let Err(error) = server::host(&mut commands, addr, &registry) {
+                                room.status = format!("Can't host on {}: {}", addr, error);
+                                continue;
+                            }
+                            server_addr = server::loopback(addr.port());
+                        }
+                        room.status = match client::connect(&mut commands, server_addr) {
+                            Ok(()) => format!("Connecting to {}", server_addr),
+                            Err(error) => error.to_string(),
+                        };
+                    }
+                    ButtonType::Ready => {
+                        if let Some(client) = client.as_mut() {
+                            room.ready = !room.ready;
+                            if room.ready {
+                                let deck = decklists.deck_for_match(&registry);
+                                room.status = format!("Playing {}", deck.name);
+                                client.send_message(ClientChannel::Command, encode(&ClientMessage::Deck(deck)));
+                            }
+                            let message = encode(&ClientMessage::Ready(room.ready));
+                            client.send_message(ClientChannel::Command, message);
+                        }
+                    }
+                    ButtonType::Back => {
+                        client::disconnect(&mut commands);
+                        server::stop_hosting(&mut commands);
+                        *room = Room::default();
+                        *local_data = LocalData::offline();
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = button_colors.hovered.into();
+            }
+            Interaction::None => {
+                *color = button_colors.normal.into();
+            }
+        }
+    }
+}
+
+fn on_server_message(
+    mut commands: Commands,
+    mut events: EventReader<EvtServerMessage>,
+    mut room: ResMut<Room>,
+    mut local_data: ResMut<LocalData>,
+    mut next_state: ResMut<NextState<AppState>>,
+    registry: Res<CardRegistry>,
+) {
+    for evt in events.read() {
+        match &**evt {
+            ServerMessage::Welcome { id, seat } => {
+                room.my_id = Some(*id);
+                *local_data = LocalData::online(*id, *seat);
+                room.status = format!("Seated as {:?}", seat);
+            }
+            ServerMessage::Refused { reason } => {
+                room.status = format!("Refused: {}", reason);
+                client::disconnect(&mut commands);
+            }
+            ServerMessage::Lobby(entries) => {
+                room.lobby = entries.clone();
+            }
+            ServerMessage::DeckRejected { reason } => {
+                room.status = format!("Deck refused: {}", reason);
+            }
+            ServerMessage::MatchStarted { seat, state } => {
+                // only card ids come over the wire, the definitions are our own
+                let mut state = state.as_ref().clone();
+                if let Err(error) = state.link(&registry) {
+                    room.status = format!("Can't play with this server: {}", error);
+                    client::disconnect(&mut commands);
+                    continue;
+                }
+                if let Some(id) = room.my_id {
+                    *local_data = LocalData::online(id, *seat);
+                }
+                commands.insert_resource::<MatchState>(state);
+                next_state.set(AppState::Playing);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_room_text(
+    room: Res<Room>,
+    connection_status: Res<ConnectionStatus>,
+    mut address_text: Query<&mut Text, (With<AddressText>, Without<PlayersText>, Without<StatusText>)>,
+    mut players_text: Query<&mut Text, (With<PlayersText>, Without<AddressText>, Without<StatusText>)>,
+    mut status_text: Query<&mut Text, (With<StatusText>, Without<AddressText>, Without<PlayersText>)>,
+) {
+    for mut text in address_text.iter_mut() {
+        text.sections[0].value = room.address.clone();
+    }
+    for mut text in players_text.iter_mut() {
+        text.sections[0].value = room
+            .lobby
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{:?}: {}{}{}",
+                    entry.seat,
+                    entry.id,
+                    if Some(entry.id) == room.my_id { " (you)" } else { "" },
+                    if entry.ready { " - ready" } else { "" },
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    for mut text in status_text.iter_mut() {
+        text.sections[0].value = format!("{}\n{}", *connection_status, room.status);
+    }
+}
+
+fn cleanup_menu(
+    mut commands: Commands,
+    menu: Query<Entity, With<RoomMenu>>,
+) {
+    for entity in menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -5,14 +5,18 @@ pub mod battlefield;
 pub mod camera;
 pub mod card;
 pub mod deck;
+pub mod goal;
+pub mod graveyard;
 pub mod hand;
 pub mod menu;
 pub mod net;
+pub mod paddle;
 pub mod player;
 pub mod slot;
 pub mod systemsets;
 
 pub mod utils;
+use actions::ActionPlugin;
 use ball::BallPlugin;
 use bevy::prelude::*;
 use bevy_gltf_blueprints::GltfBlueprintsSet;
@@ -23,18 +27,23 @@ use bevy_rapier3d::geometry::{
 use bevy_rapier3d::prelude::*;
 use card::CardPlugin;
 use deck::DeckPlugin;
+use goal::GoalPlugin;
+use graveyard::GraveyardPlugin;
 use hand::HandPlugin;
 use menu::MenuPlugin;
 use net::NetPlugin;
+use paddle::PaddlePlugin;
+use poplar_core::{BallKinds, Effect, MatchState, PlayerId, Seat};
 use slot::SlotPlugin;
+use systemsets::SystemSetsPlugin;
 use player::PlayerPlugin;
 use utils::*;
 
-use crate::{AppState, CardsHandle};
+use crate::{AppState, BallKindsHandle, CardsHandle, Models};
 
 use self::{
     camera::PlayerCameraPlugin,
-    card::{Card, CardInfo},
+    card::{Card, CardInfo, CardPool, CardRegistry},
 };
 pub struct GamePlugin;
 
@@ -52,13 +61,20 @@ impl Plugin for GamePlugin {
         app.init_resource::<LocalData>()
             .register_type::<BlenderCollider>()
             .add_plugins((
+                // keeps everything in `PlayingSets::Main` to the match
+                SystemSetsPlugin,
+                ActionPlugin,
                 CardPlugin,
                 HandPlugin,
                 SlotPlugin,
                 DeckPlugin,
+                GraveyardPlugin,
+                GoalPlugin,
                 MenuPlugin,
                 BallPlugin,
                 PlayerPlugin,
+                NetPlugin,
+                PaddlePlugin,
                 // BattlefieldPlugin,
                 ComponentsFromGltfPlugin::default(),
             ))
@@ -72,9 +88,35 @@ impl Plugin for GamePlugin {
     }
 }
 
+/// Who is playing on this machine.
 #[derive(Resource)]
 pub struct LocalData {
-    player_id: String,
+    player_id: PlayerId,
+    seat: Seat,
+}
+
+impl LocalData {
+    pub fn offline() -> Self {
+        Self {
+            player_id: PlayerId::Local(Seat::One),
+            seat: Seat::One,
+        }
+    }
+
+    pub fn online(client_id: u64, seat: Seat) -> Self {
+        Self {
+            player_id: PlayerId::Client(client_id),
+            seat,
+        }
+    }
+
+    pub fn player_id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    pub fn seat(&self) -> Seat {
+        self.seat
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -148,14 +190,16 @@ pub(crate) fn physics_replace_proxies(
                 }
             }
         }
+        // sensors only tell what enters them, nothing bounces off
+        if name.ends_with("_sensor") {
+            commands.entity(entity).insert(Sensor);
+        }
     }
 }
 
 impl FromWorld for LocalData {
     fn from_world(world: &mut World) -> Self {
-        Self {
-            player_id: "todo".to_string(),
-        }
+        Self::offline()
     }
 }
 
@@ -167,6 +211,10 @@ fn set_up(
     mut rapier_config: ResMut<RapierConfiguration>
 ) {
     rapier_config.gravity = Vec3::new(0.0, 0.0, -9.8);
+    rapier_config.timestep_mode = TimestepMode::Fixed {
+        dt: MatchState::TIMESTEP,
+        substeps: 1,
+    };
     // commands.spawn(DirectionalLightBundle {
     //     directional_light: DirectionalLight {
     //         illuminance: light_consts::lux::OVERCAST_DAY,
@@ -199,14 +247,61 @@ fn set_up(
     // });
 }
 
+// the menu only opens once the models are in and the card pool checks out.
+// Broken ball kinds only cost the variants, the standard ball is built in
 fn check_loading(
+    mut commands: Commands,
     asset_server: Res<AssetServer>,
     cards: Res<CardsHandle>,
+    card_pools: Res<Assets<CardPool>>,
+    balls: Res<BallKindsHandle>,
+    ball_kinds: Res<Assets<BallKinds>>,
+    models: Option<Res<Models>>,
     mut state: ResMut<NextState<AppState>>,
 ) {
-    if asset_server.get_recursive_dependency_load_state(&cards.0)
-        == Some(bevy::asset::RecursiveDependencyLoadState::Loaded)
-    {
-        // state.set(AppState::MainMenu);
+    match asset_server.get_load_state(&cards.0) {
+        Some(bevy::asset::LoadState::Loaded) => {}
+        Some(bevy::asset::LoadState::Failed) => {
+            state.set(AppState::CardPoolError);
+            return;
+        }
+        _ => return,
+    }
+    let Some(pool) = card_pools.get(&cards.0) else {
+        return;
+    };
+    if !pool.is_valid() {
+        for error in pool.errors() {
+            error!("card pool: {}", error);
+        }
+        state.set(AppState::CardPoolError);
+    } else if models.is_some() {
+        let ball_kinds = match asset_server.get_load_state(&balls.0) {
+            Some(bevy::asset::LoadState::Loaded) => match ball_kinds.get(&balls.0) {
+                Some(kinds) => kinds.clone(),
+                None => return,
+            },
+            Some(bevy::asset::LoadState::Failed) => {
+                error!("ball kinds: playing with the standard ball only");
+                BallKinds::default()
+            }
+            _ => return,
+        };
+        let registry = CardRegistry::from(pool);
+        info!("card pool: {} cards", registry.len());
+        // such a card serves a standard ball instead
+        for card in registry.iter() {
+            for ability in card.abilities.iter() {
+                if let Effect::SpawnBall(kind) = &ability.effect {
+                    if !ball_kinds.contains(kind) {
+                        warn!("{}: unknown ball kind `{}`", card.id, kind);
+                    }
+                }
+            }
+        }
+        info!("ball kinds: {}", ball_kinds.len());
+        commands.insert_resource(registry);
+        commands.insert_resource(ball_kinds);
+        state.set(AppState::MainMenu);
     }
 }
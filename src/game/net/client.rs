@@ -1,22 +1,352 @@
-
 use bevy::prelude::*;
 use bevy_renet::{
-    client_connected,
-    renet::{
-        transport::{ClientAuthentication, ServerAuthentication, ServerConfig},
-        ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent,
-    },
-    transport::{NetcodeClientPlugin, NetcodeServerPlugin},
+    renet::RenetClient,
+    transport::NetcodeClientPlugin,
     RenetClientPlugin,
 };
+use poplar_core::{CardRegistry, MatchState};
+use renet::transport::{
+    ClientAuthentication, NetcodeClientTransport, NetcodeError, NetcodeTransportError,
+};
+use std::{
+    fmt,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, SystemTime},
+};
+use thiserror::Error;
+
+use crate::{game::actions::EvtMatch, AppState};
+
+use super::lag::{report_lag, Delayed, LagSimulator};
+use super::prediction::Prediction;
+use super::protocol::{
+    connection_config, decode, encode, ClientChannel, ClientMessage, ServerChannel, ServerMessage,
+    Snapshot, PROTOCOL_ID, PROTOCOL_VERSION,
+};
 
 pub fn client_plugin(app: &mut App) {
     app
-        .add_plugins(RenetClientPlugin);
+        .add_plugins((RenetClientPlugin, NetcodeClientPlugin))
+        .init_resource::<ConnectionStatus>()
+        .insert_resource(LagSimulator::from_env())
+        .add_event::<EvtServerMessage>()
+        .add_systems(Startup, report_lag)
+        .add_systems(Update, (
+            client_hello,
+            client_receive_messages,
+            client_receive_snapshots,
+            update_connection_status,
+        ).chain().run_if(resource_exists::<RenetClient>))
+        .add_systems(Update, (
+            handle_transport_errors,
+            reconnect,
+        ).chain())
+        .add_systems(Update, (
+            apply_server_events,
+        ).run_if(resource_exists::<MatchState>));
+}
+
+/// A message from the server, decoded.
+#[derive(Event, Deref)]
+pub struct EvtServerMessage(pub ServerMessage);
+
+/// Where the last connection went to, kept around to reconnect with the same id.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ClientConfig {
+    pub server_addr: SocketAddr,
+    pub client_id: u64,
+}
+
+impl ClientConfig {
+    pub const MAX_RECONNECTS: u32 = 3;
+    pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+}
+
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub enum ConnectionStatus {
+    #[default]
+    Offline,
+    Connecting,
+    Connected,
+    Reconnecting { attempt: u32 },
+    Lost { reason: String, attempts: u32 },
+}
+
+impl ConnectionStatus {
+    pub fn is_connected(&self) -> bool {
+        *self == ConnectionStatus::Connected
+    }
+}
+
+impl fmt::Display for ConnectionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionStatus::Offline => write!(f, "Offline"),
+            ConnectionStatus::Connecting => write!(f, "Connecting"),
+            ConnectionStatus::Connected => write!(f, "Connected"),
+            ConnectionStatus::Reconnecting { attempt } => {
+                write!(f, "Reconnecting ({}/{})", attempt, ClientConfig::MAX_RECONNECTS)
+            }
+            ConnectionStatus::Lost { reason, .. } => write!(f, "Connection lost: {}", reason),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ConnectError {
+    #[error("can't open a socket: {0}")]
+    Socket(#[from] std::io::Error),
+    #[error("can't set up netcode: {0}")]
+    Netcode(#[from] NetcodeError),
+}
+
+/// Inserts a `RenetClient` connecting to `server_addr`.
+pub fn connect(commands: &mut Commands, server_addr: SocketAddr) -> Result<(), ConnectError> {
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    let config = ClientConfig {
+        server_addr,
+        client_id: current_time.as_millis() as u64,
+    };
+    open_transport(commands, config)?;
+    commands.insert_resource(config);
+    commands.insert_resource(ConnectionStatus::Connecting);
+    Ok(())
+}
+
+fn open_transport(commands: &mut Commands, config: ClientConfig) -> Result<(), ConnectError> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let current_time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap();
+    let authentication = ClientAuthentication::Unsecure {
+        client_id: config.client_id,
+        protocol_id: PROTOCOL_ID,
+        server_addr: config.server_addr,
+        user_data: None,
+    };
+    let transport = NetcodeClientTransport::new(current_time, authentication, socket)?;
+    commands.insert_resource(RenetClient::new(connection_config()));
+    commands.insert_resource(transport);
+    Ok(())
+}
+
+pub fn disconnect(commands: &mut Commands) {
+    close_transport(commands);
+    commands.remove_resource::<ClientConfig>();
+    commands.insert_resource(ConnectionStatus::Offline);
 }
 
+fn close_transport(commands: &mut Commands) {
+    commands.remove_resource::<RenetClient>();
+    commands.remove_resource::<NetcodeClientTransport>();
+}
+
+/// Run condition for everything that has to go through the server.
+pub fn is_online(status: Res<ConnectionStatus>) -> bool {
+    status.is_connected()
+}
+
+// the server ignores everything until it got our hello
+fn client_hello(mut client: ResMut<RenetClient>, mut greeted: Local<bool>) {
+    if !client.is_connected() {
+        *greeted = false;
+        return;
+    }
+    if !*greeted {
+        let hello = encode(&ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+        });
+        client.send_message(ClientChannel::Command, hello);
+        *greeted = true;
+    }
+}
+
+fn client_receive_messages(
+    mut arriving: Local<Delayed<ServerMessage>>,
+    time: Res<Time<Real>>,
+    mut lag: ResMut<LagSimulator>,
+    mut client: ResMut<RenetClient>,
+    mut events: EventWriter<EvtServerMessage>,
+) {
+    while let Some(message) = client.receive_message(ServerChannel::ServerMessages) {
+        match decode::<ServerMessage>(&message) {
+            Ok(message) => arriving.send_reliable(&mut lag, time.elapsed(), message),
+            Err(error) => warn!("{}", error),
+        }
+    }
+    events.send_batch(arriving.arrived(time.elapsed()).into_iter().map(EvtServerMessage));
+}
+
+// the prediction catches up with the newest snapshot on its next tick
+fn client_receive_snapshots(
+    mut arriving: Local<Delayed<Snapshot>>,
+    time: Res<Time<Real>>,
+    mut lag: ResMut<LagSimulator>,
+    mut client: ResMut<RenetClient>,
+    mut prediction: ResMut<Prediction>,
+) {
+    while let Some(message) = client.receive_message(ServerChannel::NetworkedEntities) {
+        if let Ok(snapshot) = decode::<Snapshot>(&message) {
+            arriving.send(&mut lag, time.elapsed(), snapshot);
+        }
+    }
+    for snapshot in arriving.arrived(time.elapsed()) {
+        prediction.receive(snapshot);
+    }
+}
+
+// the server already applied these, the local view of the match just catches up
+fn apply_server_events(
+    mut events: EventReader<EvtServerMessage>,
+    registry: Res<CardRegistry>,
+    mut match_state: ResMut<MatchState>,
+    mut match_events: EventWriter<EvtMatch>,
+) {
+    for evt in events.read() {
+        match &**evt {
+            ServerMessage::Match(server_events) => {
+                for event in server_events {
+                    if let Err(error) = match_state.apply_seen_event(event, &registry) {
+                        warn!("the server revealed a card we don't have: {}", error);
+                    }
+                    match_events.send(EvtMatch(event.clone()));
+                }
+            }
+            ServerMessage::ActionRejected { action, reason } => {
+                info!("the server refused {:?}: {}", action, reason);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn update_connection_status(
+    mut commands: Commands,
+    client: Res<RenetClient>,
+    mut status: ResMut<ConnectionStatus>,
+) {
+    if client.is_connected() {
+        if !status.is_connected() {
+            info!("connected");
+            *status = ConnectionStatus::Connected;
+        }
+    } else if client.is_disconnected() {
+        let reason = client
+            .disconnect_reason()
+            .map_or("unknown".to_string(), |reason| reason.to_string());
+        lose_connection(&mut commands, &mut status, reason);
+    }
+}
+
+fn handle_transport_errors(
+    mut commands: Commands,
+    mut errors: EventReader<NetcodeTransportError>,
+    mut status: ResMut<ConnectionStatus>,
+) {
+    for error in errors.read() {
+        warn!("{}", error);
+        lose_connection(&mut commands, &mut status, error.to_string());
+    }
+}
+
+fn lose_connection(commands: &mut Commands, status: &mut ConnectionStatus, reason: String) {
+    close_transport(commands);
+    let attempts = match *status {
+        ConnectionStatus::Reconnecting { attempt } => attempt,
+        ConnectionStatus::Lost { attempts, .. } => attempts,
+        _ => 0,
+    };
+    *status = ConnectionStatus::Lost { reason, attempts };
+}
+
+// the server drops a match when a seat empties, so only a room can be rejoined
+fn reconnect(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut timer: Local<Timer>,
+    config: Option<Res<ClientConfig>>,
+    mut status: ResMut<ConnectionStatus>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let ConnectionStatus::Lost { attempts, .. } = *status else {
+        *timer = Timer::new(ClientConfig::RECONNECT_DELAY, TimerMode::Once);
+        return;
+    };
+    match state.get() {
+        AppState::Playing => {
+            next_state.set(AppState::RoomMenu);
+        }
+        AppState::RoomMenu => {}
+        _ => return,
+    }
+    let Some(config) = config else {
+        return;
+    };
+    if attempts >= ClientConfig::MAX_RECONNECTS || !timer.tick(time.delta()).finished() {
+        return;
+    }
+    let attempt = attempts + 1;
+    match open_transport(&mut commands, *config) {
+        Ok(()) => *status = ConnectionStatus::Reconnecting { attempt },
+        Err(error) => {
+            *status = ConnectionStatus::Lost {
+                reason: error.to_string(),
+                attempts: attempt,
+            }
+        }
+    }
+    timer.reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::UdpSocket, thread};
+
+    use bevy::ecs::{event::ManualEventReader, system::RunSystemOnce};
+    use bevy_renet::renet::RenetServer;
+    use poplar_core::{CardRegistry, Seat};
+    use renet_server::renet_server_on;
+
+    use super::*;
+    use crate::game::net::server::server_plugin;
+
+    // the game hosting a match and joining it, like a host does
+    #[test]
+    fn hello_handshake() {
+        // whatever port is free, tests run side by side
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_addr = socket.local_addr().unwrap();
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_state::<AppState>()
+            .init_resource::<Prediction>()
+            .add_plugins((client_plugin, server_plugin))
+            .insert_resource(LagSimulator::default());
+        let (server, transport) = renet_server_on(socket, vec![server_addr]).unwrap();
+        app.insert_resource(server)
+            .insert_resource(transport)
+            .insert_resource(CardRegistry::default());
+        app.world
+            .run_system_once(move |mut commands: Commands| connect(&mut commands, server_addr).unwrap());
+
+        let mut reader = ManualEventReader::<EvtServerMessage>::default();
+        let mut welcome = None;
+        for _ in 0..300 {
+            app.update();
+            let events = app.world.resource::<Events<EvtServerMessage>>();
+            for evt in reader.read(events) {
+                if let ServerMessage::Welcome { id, seat } = **evt {
+                    welcome = Some((id, seat));
+                }
+            }
+            if welcome.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
 
-pub enum ClientChannel {
-    Input,
-    Command,
+        assert_eq!(*app.world.resource::<ConnectionStatus>(), ConnectionStatus::Connected);
+        let client_id = app.world.resource::<ClientConfig>().client_id;
+        assert_eq!(welcome, Some((client_id, Seat::One)));
+        assert_eq!(app.world.resource::<RenetServer>().connected_clients(), 1);
+    }
 }
@@ -0,0 +1,132 @@
+//! A bad network on demand, to try prediction against a server on the same machine.
+//!
+//! Set in the environment before starting the game:
+//!
+//! ```text
+//! POPLAR_LATENCY=80 POPLAR_JITTER=20 POPLAR_PACKET_LOSS=10 cargo run
+//! ```
+//!
+//! Latency and jitter are milliseconds each way, packet loss is a percent. Lost snapshots never arrive,
+//! lost inputs and server messages arrive a resend later like renet would deliver them.
+
+use std::{collections::VecDeque, env, time::Duration};
+
+use bevy::prelude::*;
+use poplar_core::{random_seed, MatchRng};
+
+/// How bad the network pretends to be, all zero by default.
+#[derive(Resource, Debug)]
+pub struct LagSimulator {
+    /// added to every message, each way
+    pub latency: Duration,
+    /// the latency varies by up to this much either way
+    pub jitter: Duration,
+    /// percent of the messages lost
+    pub packet_loss: u32,
+    rng: MatchRng,
+}
+
+impl LagSimulator {
+    pub fn new(latency: Duration, jitter: Duration, packet_loss: u32) -> Self {
+        Self {
+            latency,
+            jitter,
+            packet_loss: packet_loss.min(100),
+            rng: MatchRng::new(random_seed()),
+        }
+    }
+
+    /// Reads `POPLAR_LATENCY`, `POPLAR_JITTER` and `POPLAR_PACKET_LOSS`, a missing or unreadable one is zero.
+    pub fn from_env() -> Self {
+        let read = |name: &str| env::var(name).ok().and_then(|value| value.parse::<u64>().ok()).unwrap_or(0);
+        Self::new(
+            Duration::from_millis(read("POPLAR_LATENCY")),
+            Duration::from_millis(read("POPLAR_JITTER")),
+            read("POPLAR_PACKET_LOSS") as u32,
+        )
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.packet_loss > 0
+    }
+
+    /// What it adds to the round trip the client measures, at worst.
+    pub fn round_trip(&self) -> Duration {
+        (self.latency + self.jitter) * 2
+    }
+
+    fn delay(&mut self) -> Duration {
+        let jitter = self.jitter.as_millis() as usize;
+        let offset = self.rng.below(jitter * 2 + 1) as i64 - jitter as i64;
+        Duration::from_millis((self.latency.as_millis() as i64 + offset).max(0) as u64)
+    }
+
+    fn is_lost(&mut self) -> bool {
+        self.packet_loss > 0 && self.rng.below(100) < self.packet_loss as usize
+    }
+}
+
+impl Default for LagSimulator {
+    fn default() -> Self {
+        Self::new(Duration::ZERO, Duration::ZERO, 0)
+    }
+}
+
+/// Messages on their way, handed out once they arrived.
+pub struct Delayed<T> {
+    queue: VecDeque<(Duration, T)>,
+}
+
+impl<T> Default for Delayed<T> {
+    fn default() -> Self {
+        Self { queue: VecDeque::new() }
+    }
+}
+
+impl<T> Delayed<T> {
+    /// `message` was sent `now` on an unreliable channel, it may never arrive.
+    pub fn send(&mut self, lag: &mut LagSimulator, now: Duration, message: T) {
+        if lag.is_lost() {
+            return;
+        }
+        let arrival = now + lag.delay();
+        self.queue.push_back((arrival, message));
+    }
+
+    /// `message` was sent `now` on a reliable ordered channel: a lost one arrives a resend later,
+    /// none overtakes the ones sent before it.
+    pub fn send_reliable(&mut self, lag: &mut LagSimulator, now: Duration, message: T) {
+        let mut arrival = now + lag.delay();
+        if lag.is_lost() {
+            arrival += lag.round_trip();
+        }
+        if let Some((last, _)) = self.queue.back() {
+            arrival = arrival.max(*last);
+        }
+        self.queue.push_back((arrival, message));
+    }
+
+    /// The messages that arrived by `now`, in the order they were sent.
+    pub fn arrived(&mut self, now: Duration) -> Vec<T> {
+        let mut arrived = Vec::new();
+        let mut waiting = VecDeque::with_capacity(self.queue.len());
+        for (arrival, message) in self.queue.drain(..) {
+            if arrival <= now {
+                arrived.push(message);
+            } else {
+                waiting.push_back((arrival, message));
+            }
+        }
+        self.queue = waiting;
+        arrived
+    }
+}
+
+pub(super) fn report_lag(lag: Res<LagSimulator>) {
+    if lag.is_active() {
+        info!(
+            "simulating {:?} latency, {:?} jitter and {}% packet loss",
+            lag.latency, lag.jitter, lag.packet_loss
+        );
+    }
+}
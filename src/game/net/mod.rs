@@ -1,4 +1,6 @@
 pub mod client;
+pub mod lag;
+pub mod prediction;
 pub mod server;
 pub mod protocol;
 
@@ -11,6 +13,7 @@ impl Plugin for NetPlugin {
         app
             .add_plugins((
                 client::client_plugin,
+                prediction::prediction_plugin,
                 server::server_plugin,
             ));
     }
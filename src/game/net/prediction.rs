@@ -0,0 +1,238 @@
+//! Online the server runs the balls and paddles, its snapshots arrive a half round trip late.
+//! The client runs its copy of the match ahead of them instead: its own paddle from the keys,
+//! everything else the way it was heading. Every snapshot the prediction got wrong puts the copy back
+//! where the server was and plays the inputs made since over again, the display eases into the difference.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_renet::renet::RenetClient;
+use poplar_core::{BallId, MatchState, Seat};
+
+use crate::{
+    game::{paddle::PaddleInput, systemsets::PlayingSets, LocalData},
+    AppState,
+};
+
+use super::{
+    client::is_online,
+    lag::{Delayed, LagSimulator},
+    protocol::{encode, ClientChannel, PlayerInput, Snapshot},
+};
+
+/// The prediction of the running match, reset whenever one starts.
+#[derive(Resource, Default, Debug)]
+pub struct Prediction {
+    /// the newest snapshot not reconciled yet
+    snapshot: Option<Snapshot>,
+    /// tick of the last snapshot reconciled, older ones come too late
+    confirmed: u64,
+    /// the inputs of the local player by tick, the server has not necessarily seen them yet
+    inputs: VecDeque<PlayerInput>,
+    /// the last times the keys changed, sent to the server again every tick
+    changes: VecDeque<PlayerInput>,
+    /// what the predicted ticks summed to, to tell a right prediction without redoing it
+    checksums: VecDeque<(u64, u64)>,
+    /// how far the display still is from a corrected ball, it shrinks away
+    ball_offsets: HashMap<BallId, Vec2>,
+    paddle_offsets: [f32; 2],
+    /// snapshots that didn't match what was predicted
+    pub mispredictions: u32,
+}
+
+impl Prediction {
+    /// a second or two of inputs and checksums, a longer lag can't be predicted over anyway
+    const HISTORY: usize = MatchState::TICK_RATE as usize * 2;
+    /// ticks ahead of the server's past its input has to travel, in case it takes longer
+    const MARGIN: u64 = 2;
+    /// the prediction may run this many ticks further ahead before it is pulled back
+    const MAX_DRIFT: u64 = 8;
+    /// seconds for a correction to shrink to a third
+    const SMOOTHING: f32 = 0.1;
+    /// a correction this far jumps, easing into it would look worse
+    const SNAP: f32 = 0.5;
+
+    /// Keeps the newest of the snapshots that arrived.
+    pub fn receive(&mut self, snapshot: Snapshot) {
+        let newest = self.snapshot.as_ref().map_or(self.confirmed, |pending| pending.tick);
+        if snapshot.tick > newest {
+            self.snapshot = Some(snapshot);
+        }
+    }
+
+    /// Where a ball is drawn compared to where the prediction has it.
+    pub fn ball_offset(&self, ball: BallId) -> Vec2 {
+        self.ball_offsets.get(&ball).copied().unwrap_or_default()
+    }
+
+    /// Where a paddle is drawn across the table compared to where the prediction has it.
+    pub fn paddle_offset(&self, seat: Seat) -> f32 {
+        self.paddle_offsets[seat.index()]
+    }
+
+    // how the local player steered during `tick`
+    fn input_at(&self, tick: u64) -> Option<&PlayerInput> {
+        self.inputs.iter().rev().find(|input| input.tick <= tick)
+    }
+
+    fn checksum_at(&self, tick: u64) -> Option<u64> {
+        self.checksums.iter().find(|(at, _)| *at == tick).map(|(_, sum)| *sum)
+    }
+
+    fn record(&mut self, match_state: &MatchState) {
+        self.checksums.push_back((match_state.tick, match_state.checksum()));
+        if self.checksums.len() > Self::HISTORY {
+            self.checksums.pop_front();
+        }
+    }
+}
+
+pub fn prediction_plugin(app: &mut App) {
+    app.init_resource::<Prediction>()
+        .add_systems(OnEnter(AppState::Playing), reset_prediction)
+        .add_systems(
+            FixedUpdate,
+            (reconcile, predict)
+                .chain()
+                .in_set(PlayingSets::Main)
+                .run_if(is_online)
+                .run_if(resource_exists::<RenetClient>)
+                .run_if(resource_exists::<MatchState>),
+        )
+        .add_systems(Update, smooth_corrections.in_set(PlayingSets::Main));
+}
+
+fn reset_prediction(mut commands: Commands) {
+    commands.insert_resource(Prediction::default());
+}
+
+// the server had the state of the snapshot at its tick. Unless the prediction got there as well
+// the copy starts over from it, the ticks predicted since are played again with the same inputs
+fn reconcile(
+    client: Res<RenetClient>,
+    lag: Res<LagSimulator>,
+    local_data: Res<LocalData>,
+    mut prediction: ResMut<Prediction>,
+    mut match_state: ResMut<MatchState>,
+) {
+    let Some(snapshot) = prediction.snapshot.take() else {
+        return;
+    };
+    prediction.confirmed = snapshot.tick;
+    for (seat, health) in Seat::ALL.into_iter().zip(snapshot.health) {
+        match_state.player_mut(seat).health = health;
+    }
+    // far enough ahead for an input to reach the server before its tick does
+    let round_trip = client.rtt() as f32 + lag.round_trip().as_secs_f32();
+    let lead = (round_trip / MatchState::TIMESTEP).ceil() as u64 + Prediction::MARGIN;
+    let predicted = match_state.tick;
+    let in_step = predicted + Prediction::MARGIN >= snapshot.tick + lead
+        && predicted <= snapshot.tick + lead + Prediction::MAX_DRIFT;
+    if in_step && prediction.checksum_at(snapshot.tick) == Some(snapshot.checksum) {
+        return;
+    }
+    prediction.mispredictions += 1;
+
+    let old_balls = std::mem::take(&mut match_state.balls);
+    let old_paddles = Seat::ALL.map(|seat| match_state.paddle(seat).x);
+    // balls served after the snapshot was taken came with their event already
+    let newest = snapshot.balls.iter().map(|ball| ball.id).max();
+    match_state.balls = snapshot.balls;
+    match_state.balls.extend(
+        old_balls
+            .iter()
+            .filter(|ball| newest.is_some_and(|newest| ball.id > newest))
+            .cloned(),
+    );
+    for (seat, paddle) in Seat::ALL.into_iter().zip(snapshot.paddles) {
+        *match_state.paddle_mut(seat) = paddle;
+    }
+    match_state.tick = snapshot.tick;
+    prediction.checksums.clear();
+    let target = if in_step { predicted } else { snapshot.tick + lead };
+    let seat = local_data.seat();
+    while match_state.tick < target {
+        if let Some(input) = prediction.input_at(match_state.tick + 1) {
+            match_state.steer(seat, input.direction());
+        }
+        match_state.predict(MatchState::TIMESTEP);
+        prediction.record(&match_state);
+    }
+    // pulled back, the inputs made for ticks that won't come are made again
+    let tick = match_state.tick;
+    prediction.inputs.retain(|input| input.tick <= tick);
+    while prediction.inputs.len() > 1 && prediction.inputs[1].tick <= snapshot.tick {
+        prediction.inputs.pop_front();
+    }
+
+    // the display stays where it was and eases into the correction
+    for ball in match_state.balls.iter() {
+        let Some(old) = old_balls.iter().find(|old| old.id == ball.id) else {
+            continue;
+        };
+        let offset = prediction.ball_offset(ball.id) + old.position - ball.position;
+        if offset.length() < Prediction::SNAP {
+            prediction.ball_offsets.insert(ball.id, offset);
+        } else {
+            prediction.ball_offsets.remove(&ball.id);
+        }
+    }
+    for (seat, old) in Seat::ALL.into_iter().zip(old_paddles) {
+        let offset = prediction.paddle_offset(seat) + old - match_state.paddle(seat).x;
+        prediction.paddle_offsets[seat.index()] = if offset.abs() < Prediction::SNAP { offset } else { 0.0 };
+    }
+}
+
+// the local paddle goes the way the keys say right away, the server hears of it before it gets to that tick
+fn predict(
+    mut outgoing: Local<Delayed<Vec<u8>>>,
+    time: Res<Time<Real>>,
+    paddle_input: Res<PaddleInput>,
+    local_data: Res<LocalData>,
+    mut lag: ResMut<LagSimulator>,
+    mut client: ResMut<RenetClient>,
+    mut prediction: ResMut<Prediction>,
+    mut match_state: ResMut<MatchState>,
+) {
+    let input = PlayerInput {
+        tick: match_state.tick + 1,
+        ..**paddle_input
+    };
+    let changed = prediction
+        .inputs
+        .back()
+        .map_or(input.direction() != 0, |last| last.direction() != input.direction());
+    if changed {
+        prediction.changes.push_back(input);
+        if prediction.changes.len() > PlayerInput::RESENT {
+            prediction.changes.pop_front();
+        }
+    }
+    if !prediction.changes.is_empty() {
+        let changes: Vec<PlayerInput> = prediction.changes.iter().copied().collect();
+        outgoing.send(&mut lag, time.elapsed(), encode(&changes));
+    }
+    for message in outgoing.arrived(time.elapsed()) {
+        client.send_message(ClientChannel::Input, message);
+    }
+    prediction.inputs.push_back(input);
+    if prediction.inputs.len() > Prediction::HISTORY {
+        prediction.inputs.pop_front();
+    }
+    match_state.steer(local_data.seat(), input.direction());
+    match_state.predict(MatchState::TIMESTEP);
+    // only for the cooldown overlay, the server decides when a card may act and restarts the countdown
+    match_state.cool_down(MatchState::TIMESTEP);
+    prediction.record(&match_state);
+}
+
+fn smooth_corrections(time: Res<Time>, mut prediction: ResMut<Prediction>) {
+    let shrink = (-time.delta_seconds() / Prediction::SMOOTHING).exp();
+    prediction.ball_offsets.retain(|_, offset| {
+        *offset *= shrink;
+        offset.length() > 0.001
+    });
+    for offset in prediction.paddle_offsets.iter_mut() {
+        *offset *= shrink;
+    }
+}
@@ -1,3 +1,2 @@
-use bevy::prelude::*;
-use serde::{Serialize, Deserialize};
-
+// the protocol is shared with renet_server, so it lives in poplar_core
+pub use poplar_core::protocol::*;
@@ -1,32 +1,42 @@
-
 use bevy::prelude::*;
 use bevy_renet::{
-    client_connected,
-    renet::{
-        transport::{ClientAuthentication, ServerAuthentication, ServerConfig},
-        ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent,
-    },
-    transport::{NetcodeClientPlugin, NetcodeServerPlugin},
+    renet::{transport::NetcodeServerTransport, RenetServer},
+    transport::NetcodeServerPlugin,
     RenetServerPlugin,
 };
-use renet::{
-    transport::{NetcodeClientTransport, NetcodeServerTransport, NetcodeTransportError},
-    ClientId,
-};
+use renet_server::{new_renet_server, Lobby, MatchServerPlugin, ServerMatch};
+
+use crate::game::card::CardRegistry;
+use std::net::{Ipv4Addr, SocketAddr};
 
-use std::time::SystemTime;
-use std::{collections::HashMap, net::UdpSocket};
 
 pub fn server_plugin(app: &mut App) {
     app
-        .add_plugins(RenetServerPlugin);
+        .add_plugins((RenetServerPlugin, NetcodeServerPlugin, MatchServerPlugin));
+}
 
-    let server = RenetServer::new(ConnectionConfig::default());
-    app.insert_resource(server);
+/// Starts the match server inside the game, listening on every interface.
+/// Peers join through `public_addr`, the host itself through loopback.
+pub fn host(
+    commands: &mut Commands,
+    public_addr: SocketAddr,
+    registry: &CardRegistry,
+) -> Result<(), std::io::Error> {
+    let bind_addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, public_addr.port()));
+    let (server, transport) = new_renet_server(bind_addr, vec![public_addr, loopback(public_addr.port())])?;
+    commands.insert_resource(server);
+    commands.insert_resource(transport);
+    commands.insert_resource(registry.clone());
+    Ok(())
 }
 
+pub fn loopback(port: u16) -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, port))
+}
 
-pub enum ServerChannel {
-    ServerMessages,
-    NetworkedEntities,
+pub fn stop_hosting(commands: &mut Commands) {
+    commands.remove_resource::<RenetServer>();
+    commands.remove_resource::<NetcodeServerTransport>();
+    commands.remove_resource::<ServerMatch>();
+    commands.insert_resource(Lobby::default());
 }
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use poplar_core::{MatchState, PaddleState, Seat, Table};
+
+use crate::AppState;
+
+use super::{
+    ball::Ball,
+    card::HoverPoint,
+    net::{client::ConnectionStatus, prediction::Prediction, protocol::PlayerInput},
+    systemsets::PlayingSets,
+    LocalData,
+};
+
+pub struct PaddlePlugin;
+
+impl Plugin for PaddlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PaddleInput>()
+            .add_systems(OnEnter(AppState::Playing), spawn_paddles)
+            .add_systems(OnExit(AppState::Playing), despawn_paddles)
+            .add_systems(
+                Update,
+                (steer_paddle, place_paddles)
+                    .in_set(PlayingSets::Main)
+                    .run_if(resource_exists::<MatchState>),
+            );
+    }
+}
+
+/// How the local player steers their paddle right now.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct PaddleInput(pub PlayerInput);
+
+/// The paddle of a player, a kinematic body that follows its `PaddleState`.
+/// Its mesh and collider are made for [`PaddleState::WIDTH`], cards change its width through the scale.
+#[derive(Component)]
+pub struct Paddle {
+    pub seat: Seat,
+}
+
+impl Paddle {
+    const HEIGHT: f32 = 0.2;
+    /// where the balls roll
+    const Z: f32 = Ball::TABLE_TOP + Self::HEIGHT / 2.0;
+}
+
+fn spawn_paddles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    local_data: Res<LocalData>,
+) {
+    let mesh = meshes.add(Cuboid::new(PaddleState::WIDTH, PaddleState::THICKNESS, Paddle::HEIGHT));
+    let table = Table::default();
+    for seat in Seat::ALL {
+        let paddle = PaddleState::new(seat);
+        let color = if seat == local_data.seat() {
+            Color::rgb_u8(230, 230, 230)
+        } else {
+            Color::rgb_u8(200, 60, 60)
+        };
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                material: materials.add(color),
+                transform: Transform::from_xyz(paddle.x, paddle.y(&table), Paddle::Z),
+                ..default()
+            },
+            Name::new(format!("Paddle {:?}", seat)),
+            Paddle { seat },
+            RigidBody::KinematicPositionBased,
+            Collider::cuboid(PaddleState::WIDTH / 2.0, PaddleState::THICKNESS / 2.0, Paddle::HEIGHT / 2.0),
+        ));
+    }
+}
+
+fn despawn_paddles(mut commands: Commands, paddles: Query<Entity, With<Paddle>>) {
+    for entity in paddles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// `A`/`D` steer the paddle, so does holding the right mouse button: it goes after the pointer.
+// Online the prediction sends it to the server along with the tick it was made for
+fn steer_paddle(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    hover_point: Res<HoverPoint>,
+    local_data: Res<LocalData>,
+    status: Res<ConnectionStatus>,
+    mut paddle_input: ResMut<PaddleInput>,
+    mut match_state: ResMut<MatchState>,
+) {
+    let seat = local_data.seat();
+    let mut input = PlayerInput {
+        left: keys.pressed(KeyCode::KeyA),
+        right: keys.pressed(KeyCode::KeyD),
+        ..default()
+    };
+    if let (true, HoverPoint::Some(point)) = (mouse.pressed(MouseButton::Right), &*hover_point) {
+        // close enough counts as there, or the paddle would jitter around the pointer
+        let offset = point.x - match_state.paddle(seat).x;
+        input.left |= offset < -PaddleState::THICKNESS;
+        input.right |= offset > PaddleState::THICKNESS;
+    }
+    if !status.is_connected() {
+        match_state.steer(seat, input.direction());
+    }
+    **paddle_input = input;
+}
+
+// the bodies follow the rules, online eased into the corrections of the prediction
+fn place_paddles(
+    match_state: Res<MatchState>,
+    prediction: Res<Prediction>,
+    mut paddles: Query<(&Paddle, &mut Transform)>,
+) {
+    let table = match_state.table;
+    for (paddle, mut transform) in paddles.iter_mut() {
+        let state = match_state.paddle(paddle.seat);
+        transform.translation.x = state.x + prediction.paddle_offset(paddle.seat);
+        transform.translation.y = state.y(&table);
+        transform.scale.x = state.width(&table) / PaddleState::WIDTH;
+    }
+}
@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 use bevy::utils::HashMap;
+use poplar_core::{MatchState, PlayerId, PlayerState, Seat};
+
+use crate::AppState;
+
+use super::{actions::EvtMatch, LocalData};
 
 pub struct PlayerPlugin;
 
@@ -8,35 +13,41 @@ impl Plugin for PlayerPlugin {
         app
             .insert_resource(HealthBarMap(HashMap::new()))
             .add_event::<EvtBeHurt>()
+            .add_event::<EvtEnergyRefused>()
             .add_systems(Startup, setup)
+            .add_systems(OnEnter(AppState::Playing), seat_local_player)
             .add_systems(Update, (
                 update_health_bar,
+                update_energy_bar,
                 decrease_health,
                 test_health,
-            ));
+            ).run_if(resource_exists::<MatchState>));
 
     }
 }
 
-#[derive(Component)]
+/// Ties hands, slots and the health bar to whoever owns them.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Player {
-    player_id: String,
-    health: i32,
+    player_id: PlayerId,
+    seat: Seat,
 }
 
-impl Default for Player {
-    fn default() -> Player {
-        Self {
-            player_id: "todo".to_string(),
-            health: Player::DEFAULT_HEALTH,
-        }
+impl Player {
+    pub fn new(player_id: PlayerId, seat: Seat) -> Self {
+        Self { player_id, seat }
     }
-}
 
-impl Player {
-    pub const DEFAULT_HEALTH: i32 = 20;
-    pub fn id(&self) -> String {
-        self.player_id.clone()
+    pub fn local(local_data: &LocalData) -> Self {
+        Self::new(local_data.player_id(), local_data.seat())
+    }
+
+    pub fn id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    pub fn seat(&self) -> Seat {
+        self.seat
     }
 }
 
@@ -50,6 +61,16 @@ struct HealthBarMain;
 #[derive(Component)]
 struct HealthBar;
 
+#[derive(Component)]
+struct EnergyBar;
+
+#[derive(Component)]
+struct EnergyText;
+
+/// A card was not played because the local player can't pay for it, the energy bar flashes.
+#[derive(Event)]
+pub struct EvtEnergyRefused;
+
 fn setup(
     mut commands: Commands,
     mut health_bar_map: ResMut<HealthBarMap>,
@@ -84,6 +105,46 @@ fn setup(
         }).insert(HealthBar);
     }).insert(Name::new("PlayerHealthBar")).id();
 
+    // the energy of the local player, right under the health bar
+    commands.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(50.0),
+            height: Val::Percent(2.5),
+            left: Val::Percent(25.),
+            top: Val::Percent(30.5),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        background_color: Color::MIDNIGHT_BLUE.into(),
+        ..default()
+    }).with_children(|parent| {
+        parent.spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(0.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::CYAN.into(),
+            ..default()
+        }).insert(EnergyBar);
+    }).insert(Name::new("PlayerEnergyBar"));
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::CYAN,
+                ..default()
+            },
+        ).with_style(Style {
+            left: Val::Percent(76.),
+            top: Val::Percent(30.),
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        EnergyText,
+    ));
+
     let player_id = commands.spawn((
         Player::default(),
         Name::new("Player"),
@@ -93,6 +154,19 @@ fn setup(
 }
 
 
+// the health bar is spawned once, it belongs to whoever plays this match
+fn seat_local_player(
+    local_data: Res<LocalData>,
+    health_bar_map: Res<HealthBarMap>,
+    mut players: Query<&mut Player>,
+) {
+    for &player_entity in health_bar_map.0.keys() {
+        if let Ok(mut player) = players.get_mut(player_entity) {
+            *player = Player::local(&local_data);
+        }
+    }
+}
+
 use crate::game::menu::effect::Disappearing;
 fn update_health_bar(
     mut cmds: Commands,
@@ -100,15 +174,16 @@ fn update_health_bar(
     mut query: Query<(Entity, &Children), With<HealthBarMain>>,
     mut query2: Query<&mut Style, (With<HealthBar>, Without<HealthBarMain>)>,
     health_bar_map: ResMut<HealthBarMap>,
+    match_state: Res<MatchState>,
 ) {
     for (eid, player) in players.iter() {
         if let Some(health_bar_id) = health_bar_map.0.get(&eid) {
             if let Ok((health_bar_style_e, childs)) = query.get_mut(*health_bar_id) {
                 for &child in childs.iter() {
                     if let Ok(mut health_bar_style) = query2.get_mut(child) {
-                        let health = player.health;
+                        let health = match_state.player(player.seat).health;
                         let cur_width = health_bar_style.width.clone();
-                        let target_width = Val::Percent((health as f32 / Player::DEFAULT_HEALTH as f32) * 100.0);
+                        let target_width = Val::Percent((health as f32 / PlayerState::DEFAULT_HEALTH as f32) * 100.0);
                         health_bar_style.width = target_width;
                         if cur_width == target_width {
                             continue;
@@ -161,31 +236,61 @@ fn update_health_bar(
 }
 
 
+// regeneration shows as the bar filling up, the number only counts whole points
+fn update_energy_bar(
+    time: Res<Time>,
+    match_state: Res<MatchState>,
+    local_data: Res<LocalData>,
+    mut refused: EventReader<EvtEnergyRefused>,
+    mut flash: Local<Timer>,
+    mut bars: Query<&mut Style, With<EnergyBar>>,
+    mut texts: Query<&mut Text, With<EnergyText>>,
+) {
+    if refused.read().last().is_some() {
+        *flash = Timer::from_seconds(0.4, TimerMode::Once);
+    }
+    flash.tick(time.delta());
+    let player = match_state.player(local_data.seat());
+    let filled = (player.energy as f32 + player.energy_progress) / PlayerState::MAX_ENERGY as f32;
+    for mut style in bars.iter_mut() {
+        style.width = Val::Percent(filled.min(1.0) * 100.0);
+    }
+    for mut text in texts.iter_mut() {
+        let section = &mut text.sections[0];
+        section.value = format!("{}/{}", player.energy, PlayerState::MAX_ENERGY);
+        section.style.color = if flash.finished() { Color::CYAN } else { Color::RED };
+    }
+}
+
+/// The player in `seat` loses health, whether or not they have a health bar here.
 #[derive(Event)]
 pub struct EvtBeHurt {
-    pub player_entity: Entity,
+    pub seat: Seat,
     pub damage: i32,
 }
 
+// every hurt player goes through here, goals come from the rules tick
 fn decrease_health(
-    mut players: Query<&mut Player>,
     mut events: EventReader<EvtBeHurt>,
+    mut match_state: ResMut<MatchState>,
+    mut match_events: EventWriter<EvtMatch>,
 ) {
     for evt in events.read() {
-        let mut player: Mut<Player> = players.get_mut(evt.player_entity).unwrap();
-        player.health -= evt.damage;
+        match_events.send(EvtMatch(match_state.damage(evt.seat, evt.damage)));
     }
 }
 
 fn test_health(
     input: Res<ButtonInput<KeyCode>>,
     mut events: EventWriter<EvtBeHurt>,
-    players: Query<Entity, With<Player>>,
+    health_bar_map: Res<HealthBarMap>,
+    players: Query<&Player>,
 ) {
     if input.just_pressed(KeyCode::KeyH) {
-        for player_entity in players.iter() {
+        // only the players owning a health bar, hands and slots carry a `Player` too
+        for player in players.iter_many(health_bar_map.0.keys()) {
             events.send(EvtBeHurt {
-                player_entity,
+                seat: player.seat,
                 damage: 3,
             });
         }
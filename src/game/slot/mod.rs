@@ -2,19 +2,21 @@ use std::{mem, time::Duration};
 
 use bevy::{pbr::NotShadowCaster, prelude::*, utils::HashMap};
 use bevy_rapier3d::{na::distance, prelude::Collider};
+use poplar_core::{MatchState, PlayerState, Table, Zone};
 
 use crate::{game::card::{Card, CardBundle, CardType, HoverPoint, SelectedCard}, AppState};
 mod animations;
 use animations::SlotAnimations;
 
-use super::{player::Player, systemsets::PlayingSets};
+use super::{player::Player, systemsets::PlayingSets, LocalData};
 pub struct SlotPlugin;
 
 impl Plugin for SlotPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<HoveredSlot>()
             .init_resource::<SlotData>()
-            // .add_systems(OnEnter(AppState::Playing), spawn_slots)
+            .add_systems(OnEnter(AppState::Playing), spawn_slots)
+            .add_systems(OnExit(AppState::Playing), despawn_slots)
             .add_systems(PostUpdate, on_spawn_slot.in_set(PlayingSets::Main))
             .add_systems(Update, hover_slot.after(crate::game::card::select_card).in_set(PlayingSets::Main))
             .add_systems(Update, evaluate_slots.after(hover_slot).in_set(PlayingSets::Main));
@@ -22,28 +24,84 @@ impl Plugin for SlotPlugin {
 }
 
 //TODO: move this testing case to battlefield mod
-fn spawn_slots(mut commands: Commands) {
-    for x in -2..3 {
+// the battlefield slots sit where the rules bounce the balls off their cards
+fn spawn_slots(mut commands: Commands, local_data: Res<LocalData>) {
+    let table = Table::default();
+    for index in 0..PlayerState::BATTLEFIELD_SIZE {
+        let center = table.slot_center(local_data.seat(), index);
         commands.spawn(SlotBundle {
-            slot: Slot::new(SlotType::Battlefield, None),
-            transform: Transform::from_xyz(x as f32, 0.0, 0.0),
+            slot: Slot::new(SlotType::Battlefield, None).with_index(index),
+            player: Player::local(&local_data),
+            transform: Transform::from_translation(center.extend(0.0)),
             ..default()
         });
     }
+    commands.spawn(SlotBundle {
+        slot: Slot::new(SlotType::Field, None),
+        player: Player::local(&local_data),
+        transform: Transform::from_xyz(-2.1, -2.4, 0.0),
+        ..default()
+    });
+}
+
+// every slot of the match, the hand ones included
+fn despawn_slots(
+    mut commands: Commands,
+    mut hovered_slot: ResMut<HoveredSlot>,
+    slots: Query<Entity, With<Slot>>,
+) {
+    for entity in slots.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    *hovered_slot = HoveredSlot::default();
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Component)]
 pub enum SlotType {
     Hand,
     Battlefield,
+    /// holds the field card of a player
+    Field,
     Deck,
+    Graveyard,
+    Exile,
     Anywhere,
 }
 
+impl SlotType {
+    /// The zone of the rules the slot stands for.
+    pub fn zone(self) -> Option<Zone> {
+        match self {
+            SlotType::Hand => Some(Zone::Hand),
+            SlotType::Battlefield => Some(Zone::Battlefield),
+            SlotType::Field => Some(Zone::Field),
+            SlotType::Deck => Some(Zone::Deck),
+            SlotType::Graveyard => Some(Zone::Graveyard),
+            SlotType::Exile => Some(Zone::Exile),
+            SlotType::Anywhere => None,
+        }
+    }
+}
+
+impl From<Zone> for SlotType {
+    fn from(zone: Zone) -> Self {
+        match zone {
+            Zone::Deck => SlotType::Deck,
+            Zone::Hand => SlotType::Hand,
+            Zone::Battlefield => SlotType::Battlefield,
+            Zone::Field => SlotType::Field,
+            Zone::Graveyard => SlotType::Graveyard,
+            Zone::Exile => SlotType::Exile,
+        }
+    }
+}
+
 #[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Slot {
     slot_type: SlotType,
     slotted_entity: Option<Entity>,
+    // position of the slot in the rules, e.g. the battlefield slot index
+    index: usize,
 }
 
 impl Default for Slot {
@@ -51,6 +109,7 @@ impl Default for Slot {
         Self {
             slot_type: SlotType::Anywhere,
             slotted_entity: None,
+            index: 0,
         }
     }
 }
@@ -101,9 +160,23 @@ impl Slot {
         Self {
             slot_type,
             slotted_entity,
+            index: 0,
         }
     }
 
+    pub fn with_index(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn slotted_entity(&self) -> Option<Entity> {
+        self.slotted_entity
+    }
+
     pub fn has_slot(&self) -> bool {
         match self.slotted_entity {
             None => false,
@@ -115,12 +188,34 @@ impl Slot {
         self.slotted_entity = None;
     }
 
+    /// Whether a card of `card_type` may be dropped here to be played.
+    pub fn accepts(&self, card_type: CardType) -> bool {
+        match (self.slot_type, card_type) {
+            (SlotType::Battlefield, CardType::Creature) => self.slotted_entity.is_none(),
+            // equipment goes onto the creature already there
+            (SlotType::Battlefield, CardType::Equipment) => self.slotted_entity.is_some(),
+            // a spell resolves wherever it is dropped
+            (SlotType::Battlefield, CardType::Spell) => true,
+            // a new field card replaces the old one
+            (SlotType::Field, CardType::Field) => true,
+            (SlotType::Battlefield | SlotType::Field, _) => false,
+            _ => self.slotted_entity.is_none(),
+        }
+    }
+
     pub fn try_slotting_card(
         &mut self,
         card_entity: Entity,
+        card_type: CardType,
     ) -> bool {
+        let fits = match (self.slot_type, card_type) {
+            (SlotType::Battlefield, CardType::Creature) | (SlotType::Field, CardType::Field) => true,
+            // spells and equipment never take a slot of their own in play
+            (SlotType::Battlefield | SlotType::Field, _) => false,
+            _ => true,
+        };
         match self.slotted_entity {
-            None => {
+            None if fits => {
                 self.slotted_entity = Some(card_entity);
                 true
             }
@@ -244,12 +339,25 @@ pub fn hover_slot(
     }
 }
 
+// cards in play have to cool down before acting again, the card shows what the rules have left.
+// They count down with the match ticks, on the server when online
 fn evaluate_slots(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut slots: Query<(&mut Slot, &Transform)>,
+    match_state: Option<Res<MatchState>>,
+    slots: Query<&Slot>,
+    mut cards: Query<&mut Card>,
 ) {
-    for (mut slot, transform) in &mut slots {
-        //TODO
+    let Some(match_state) = match_state else {
+        return;
+    };
+    for slot in slots.iter().filter(|slot| slot.get_type() == SlotType::Battlefield) {
+        let Some(mut card) = slot.slotted_entity().and_then(|entity| cards.get_mut(entity).ok()) else {
+            continue;
+        };
+        let Some(cooldown) = match_state.card(card.instance).map(|instance| instance.cooldown) else {
+            continue;
+        };
+        if card.cooldown != cooldown {
+            card.cooldown = cooldown;
+        }
     }
 }
@@ -18,6 +18,9 @@ impl Plugin for SystemSetsPlugin {
             .configure_sets(Update, (
                 PlayingSets::Main.run_if(in_state(AppState::Playing)),
             ))
+            .configure_sets(FixedUpdate, (
+                PlayingSets::Main.run_if(in_state(AppState::Playing)),
+            ))
             .configure_sets(PostUpdate, (
                 PlayingSets::Main.run_if(in_state(AppState::Playing)),
             ));
@@ -3,7 +3,6 @@ use bevy::gltf::Gltf;
 use bevy::prelude::*;
 use bevy::window::WindowResolution;
 use bevy_asset_loader::prelude::*;
-use bevy_common_assets::csv::{CsvAssetPlugin, LoadedCsv};
 use bevy_editor_pls::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use bevy_rapier3d::{
@@ -12,7 +11,12 @@ use bevy_rapier3d::{
 };
 
 
-use game::{card::CardInfo, GamePlugin};
+use game::{
+    ball::kinds::BALL_KINDS_PATH,
+    card::{pool::CARD_POOL_PATH, CardPool},
+    GamePlugin,
+};
+use poplar_core::{BallKinds, MatchState};
 
 
 fn main() {
@@ -29,13 +33,13 @@ fn main() {
         }),
         ..default()
     }))
-    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
-    .add_plugins(CsvAssetPlugin::<CardInfo>::new(&["cards.csv"]))
+    // the balls move in fixed ticks like on the server, whatever the frame rate
+    .insert_resource(Time::<Fixed>::from_hz(MatchState::TICK_RATE as f64))
+    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default().in_fixed_schedule())
     .init_state::<AppState>()
     .add_loading_state(
-        LoadingState::new(AppState::Loading)
-            .continue_to_state(AppState::MainMenu)
-            .load_collection::<Models>(),
+        // `check_loading` leaves the state once the card pool is checked as well
+        LoadingState::new(AppState::Loading).load_collection::<Models>(),
     )
     .add_systems(Startup, setup)
     .add_systems(Update, (spawn_level.run_if(in_state(AppState::Loading)),))
@@ -63,7 +67,6 @@ fn spawn_level(
     mut commands: Commands,
     scene_markers: Query<&LoadedMarker>,
     mut asset_event_reader: EventReader<AssetEvent<Gltf>>,
-    mut next_state: ResMut<NextState<AppState>>,
     models: Res<Assets<bevy::gltf::Gltf>>,
 ) {
     if let Some(asset_event) = asset_event_reader.read().next() {
@@ -80,7 +83,6 @@ fn spawn_level(
                     LoadedMarker,
                     Name::new("Battlefield"),
                 ));
-                next_state.set(AppState::MainMenu);
             }
         }
     }
@@ -93,12 +95,16 @@ pub struct Models {
 }
 
 #[derive(Resource)]
-pub struct CardsHandle(Handle<LoadedCsv<CardInfo>>);
+pub struct CardsHandle(Handle<CardPool>);
+
+#[derive(Resource)]
+pub struct BallKindsHandle(Handle<BallKinds>);
 
 pub fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let cards = CardsHandle(asset_server.load("cards.csv"));
+    let cards = CardsHandle(asset_server.load(CARD_POOL_PATH));
     let id = cards.0.id();
     commands.insert_resource(cards);
+    commands.insert_resource(BallKindsHandle(asset_server.load(BALL_KINDS_PATH)));
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -108,5 +114,8 @@ pub enum AppState {
     MainMenu,
     Playing,
     RoomMenu,
+    DeckBuilder,
     GameOverMenu,
+    /// the card pool is invalid, nothing can be played
+    CardPoolError,
 }